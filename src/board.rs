@@ -0,0 +1,140 @@
+use crate::brightness::Brightness;
+use crate::dot_matrix::{DotMatrix, CHAINED_SEGMENTS};
+use crate::grayscale::{GrayMatrix, Grayscale};
+use crate::max7219::Max7219;
+use crate::scene::{Context, Scene};
+use crate::seven_segment::{SegmentMode, SevenSegment};
+use crate::snapshot::Snapshot;
+
+use anyhow::anyhow;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayKind {
+    #[default]
+    DotMatrix,
+    /// 7-segment digits decoded by the MAX7219 (Code-B)
+    SevenSegment,
+    /// 7-segment digits using our own ASCII font
+    SevenSegmentRaw,
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GrayDepth {
+    /// 4 levels
+    Gray2,
+    /// 16 levels
+    Gray4,
+}
+
+/// One chain of displays and how we draw to it
+pub enum Board {
+    DotMatrix {
+        matrix: DotMatrix,
+        grayscale: Option<Grayscale>,
+    },
+    SevenSegment(SevenSegment),
+}
+
+impl Board {
+    /// Set up the displays on a chain, `open_max` gets how many MAX7219s we need
+    pub fn open(
+        kind: DisplayKind,
+        digit_modules: usize,
+        grayscale: Option<GrayDepth>,
+        open_max: impl FnOnce(usize) -> anyhow::Result<Max7219>,
+    ) -> anyhow::Result<Board> {
+        Ok(match kind {
+            DisplayKind::DotMatrix => Board::DotMatrix {
+                matrix: DotMatrix::new(open_max(CHAINED_SEGMENTS)?, 0x0)?,
+                grayscale: grayscale.map(|depth| match depth {
+                    GrayDepth::Gray2 => Grayscale::Gray2(GrayMatrix::new()),
+                    GrayDepth::Gray4 => Grayscale::Gray4(GrayMatrix::new()),
+                }),
+            },
+            DisplayKind::SevenSegment => Board::SevenSegment(SevenSegment::new(
+                open_max(digit_modules)?,
+                SegmentMode::CodeB,
+                0x0,
+            )?),
+            DisplayKind::SevenSegmentRaw => Board::SevenSegment(SevenSegment::new(
+                open_max(digit_modules)?,
+                SegmentMode::Raw,
+                0x0,
+            )?),
+        })
+    }
+
+    pub fn is_gray(&self) -> bool {
+        matches!(
+            self,
+            Board::DotMatrix {
+                grayscale: Some(_),
+                ..
+            }
+        )
+    }
+
+    pub fn set_brightness(&mut self, brightness: Brightness) -> anyhow::Result<()> {
+        match self {
+            Board::DotMatrix { matrix, .. } => matrix.set_brightness(brightness),
+            Board::SevenSegment(segments) => segments.set_brightness(brightness),
+        }
+    }
+
+    /// Draw our scene and send it to our displays
+    pub fn render(&mut self, scene: &mut dyn Scene, context: &Context) -> anyhow::Result<()> {
+        match self {
+            Board::DotMatrix {
+                matrix,
+                grayscale: None,
+            } => {
+                matrix.clear()?;
+                scene.draw(context, matrix)?;
+                matrix.flush()
+            }
+            // our ditherer takes care of flushing
+            Board::DotMatrix {
+                matrix,
+                grayscale: Some(grayscale),
+            } => {
+                matrix.clear()?;
+                grayscale.clear();
+                scene.draw_gray(context, matrix, grayscale)
+            }
+            Board::SevenSegment(segments) => {
+                segments.clear();
+                scene.draw_segments(context, segments)?;
+                segments.flush()
+            }
+        }
+    }
+
+    /// Show our next gray sub frame
+    pub fn dither(&mut self) -> anyhow::Result<()> {
+        if let Board::DotMatrix {
+            matrix,
+            grayscale: Some(grayscale),
+        } = self
+        {
+            grayscale.render_next(matrix);
+            matrix.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        match self {
+            Board::DotMatrix {
+                grayscale: Some(grayscale),
+                ..
+            } => Ok(grayscale.snapshot()),
+            Board::DotMatrix { matrix, .. } => Ok(matrix.snapshot()),
+            Board::SevenSegment(_) => Err(anyhow!("Snapshots only work for dot matrices")),
+        }
+    }
+}
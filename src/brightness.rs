@@ -0,0 +1,503 @@
+use crate::daylight::{Daylight, Phase};
+use crate::light_sensor::{LightSensor, SensorConfig};
+use crate::max7219::{Intensity, MAX_INTENSITY};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveTime, Timelike, Utc};
+use serde::Deserialize;
+
+/// What our display should be doing right now
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Brightness {
+    /// Shutdown mode, our display memory is kept
+    Off,
+    On(Intensity),
+}
+
+/// Intensity levels for each daylight phase, which we fade between as the phases change
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrightnessSchedule {
+    pub night: Intensity,
+    pub dawn: Intensity,
+    pub day: Intensity,
+    pub dusk: Intensity,
+    /// How long we take to fade into a new phase
+    pub ramp_minutes: u32,
+    /// Turn the display off between these local times
+    pub quiet: Option<QuietPeriod>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct QuietPeriod {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl BrightnessSchedule {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (phase, level) in [
+            (Phase::Night, self.night),
+            (Phase::Dawn, self.dawn),
+            (Phase::Day, self.day),
+            (Phase::Dusk, self.dusk),
+        ] {
+            if level > MAX_INTENSITY {
+                return Err(anyhow!(
+                    "{phase:?} brightness {level} is above the max intensity {MAX_INTENSITY}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn level(&self, phase: Phase) -> Intensity {
+        match phase {
+            Phase::Night => self.night,
+            Phase::Dawn => self.dawn,
+            Phase::Day => self.day,
+            Phase::Dusk => self.dusk,
+        }
+    }
+
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        self.quiet
+            .is_some_and(|quiet| quiet.contains(now.with_timezone(&Local).time()))
+    }
+
+    /// Until we know where we are in the day (our sunrise API is down, say) we guess our phase
+    /// from our local time, so we're neither dark all day nor at full brightness all night
+    pub fn brightness(&self, daylight: &Daylight, now: DateTime<Utc>) -> Brightness {
+        if self.is_quiet(now) {
+            return Brightness::Off;
+        }
+
+        let Some((phase, started)) = daylight.phase(now) else {
+            let phase = estimated_phase(now.with_timezone(&Local).time());
+            return Brightness::On(self.level(phase));
+        };
+        let from = self.level(phase.previous()) as f64;
+        let to = self.level(phase) as f64;
+
+        let ramp_seconds = self.ramp_minutes as f64 * 60.0;
+        let progress = if ramp_seconds > 0.0 {
+            ((now - started).num_seconds() as f64 / ramp_seconds).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        Brightness::On((from + ((to - from) * progress)).round() as Intensity)
+    }
+}
+
+/// Roughly our phase at a local time, civil twilight is around 6 and 7 for most of the year
+fn estimated_phase(time: NaiveTime) -> Phase {
+    match time.hour() {
+        6 => Phase::Dawn,
+        7..=18 => Phase::Day,
+        19 => Phase::Dusk,
+        _ => Phase::Night,
+    }
+}
+
+impl Default for BrightnessSchedule {
+    fn default() -> Self {
+        BrightnessSchedule {
+            night: 0x0,
+            dawn: 0x3,
+            day: 0x8,
+            dusk: 0x3,
+            ramp_minutes: 30,
+            quiet: None,
+        }
+    }
+}
+
+impl QuietPeriod {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            // we wrap around midnight
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Brightness someone picked by hand, which beats our schedule and light sensor
+#[derive(Clone, Copy, Debug)]
+pub struct ManualBrightness {
+    pub power: bool,
+    pub intensity: Option<Intensity>,
+}
+
+impl ManualBrightness {
+    pub fn apply(&self, scheduled: Brightness) -> Brightness {
+        if !self.power {
+            Brightness::Off
+        } else {
+            self.intensity.map_or(scheduled, Brightness::On)
+        }
+    }
+
+    /// Step our intensity up or down from whatever we're showing right now
+    pub fn step(&mut self, current: Option<Brightness>, up: bool) {
+        let current = match self.intensity.map(Brightness::On).or(current) {
+            Some(Brightness::On(intensity)) => intensity,
+            _ => 0,
+        };
+        let intensity = if up {
+            (current + 1).min(MAX_INTENSITY)
+        } else {
+            current.saturating_sub(1)
+        };
+        log::info!("Manual intensity {intensity}");
+        self.intensity = Some(intensity);
+    }
+}
+
+impl Default for ManualBrightness {
+    fn default() -> Self {
+        ManualBrightness {
+            power: true,
+            intensity: None,
+        }
+    }
+}
+
+/// Pick our intensity from an ambient light sensor instead of the daylight phase
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AmbientConfig {
+    pub sensor: SensorConfig,
+    #[serde(default)]
+    pub curve: LightCurve,
+}
+
+/// Maps lux onto our intensity levels
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LightCurve {
+    /// `[lux, intensity]` pairs sorted by lux, we interpolate linearly between them
+    pub points: Vec<(f32, f32)>,
+    /// How far past the halfway point between two levels the light needs to move before we
+    /// switch, in levels. Keeps us from flickering when the light sits on a boundary.
+    pub hysteresis: f32,
+}
+
+impl LightCurve {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.points.is_empty() {
+            return Err(anyhow!("Our light curve needs at least one point"));
+        }
+        if self.points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(anyhow!("Our light curve points need to be sorted by lux"));
+        }
+        if let Some((lux, level)) = self
+            .points
+            .iter()
+            .find(|(_, level)| *level < 0.0 || *level > MAX_INTENSITY as f32)
+        {
+            return Err(anyhow!(
+                "{lux} lux maps to {level}, which is outside of our intensity levels"
+            ));
+        }
+        if self.hysteresis < 0.0 {
+            return Err(anyhow!("Our light hysteresis can't be negative"));
+        }
+
+        Ok(())
+    }
+
+    /// Where `lux` lands on our curve, between two levels
+    pub fn level(&self, lux: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if lux <= first.0 {
+            first.1
+        } else if lux >= last.0 {
+            last.1
+        } else {
+            let i = self.points.iter().position(|(x, _)| *x > lux).unwrap_or(1);
+            let (x0, y0) = self.points[i - 1];
+            let (x1, y1) = self.points[i];
+
+            y0 + ((y1 - y0) * (lux - x0) / (x1 - x0))
+        }
+    }
+
+    /// Our new intensity, only leaving `current` once we're clearly past its boundaries
+    pub fn intensity(&self, lux: f32, current: Option<Intensity>) -> Intensity {
+        let level = self.level(lux);
+        match current {
+            Some(current) if (level - current as f32).abs() < 0.5 + self.hysteresis => current,
+            _ => level.round() as Intensity,
+        }
+    }
+}
+
+impl Default for LightCurve {
+    fn default() -> Self {
+        LightCurve {
+            points: vec![(1.0, 0.0), (50.0, 3.0), (500.0, 8.0), (5000.0, 15.0)],
+            hysteresis: 0.25,
+        }
+    }
+}
+
+pub struct AmbientBrightness {
+    sensor: Box<dyn LightSensor>,
+    curve: LightCurve,
+    intensity: Option<Intensity>,
+}
+
+impl AmbientBrightness {
+    pub fn open(config: &AmbientConfig) -> anyhow::Result<AmbientBrightness> {
+        Ok(AmbientBrightness {
+            sensor: config.sensor.open()?,
+            curve: config.curve.clone(),
+            intensity: None,
+        })
+    }
+
+    /// None if we can't read our sensor
+    pub fn intensity(&mut self) -> Option<Intensity> {
+        match self.sensor.read_lux() {
+            Ok(lux) => {
+                let intensity = self.curve.intensity(lux, self.intensity);
+                log::trace!("{lux} lux -> {intensity}");
+                self.intensity = Some(intensity);
+            }
+            Err(error) => log::warn!("Couldn't read our light sensor: {error}"),
+        }
+
+        self.intensity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sunrise_sunset_api::DaylightResponse;
+    use crate::testing::at;
+    use chrono::{Duration, NaiveDate, TimeZone};
+
+    fn time(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    /// A day in June in New York
+    fn response() -> DaylightResponse {
+        DaylightResponse {
+            sunrise: at("2024-06-01T09:25:00Z"),
+            sunset: at("2024-06-02T00:20:00Z"),
+            civil_twilight_begin: at("2024-06-01T08:52:00Z"),
+            civil_twilight_end: at("2024-06-02T00:53:00Z"),
+            day_length: 53_700,
+        }
+    }
+
+    #[test]
+    fn ramps_into_each_phase() {
+        let schedule = BrightnessSchedule::default();
+        let day = Daylight::Sunset {
+            response: response(),
+        };
+        // from dawn (3) to day (8) over 30 minutes
+        assert_eq!(
+            schedule.brightness(&day, at("2024-06-01T09:25:00Z")),
+            Brightness::On(3)
+        );
+        assert_eq!(
+            schedule.brightness(&day, at("2024-06-01T09:37:00Z")),
+            Brightness::On(5)
+        );
+        assert_eq!(
+            schedule.brightness(&day, at("2024-06-01T12:00:00Z")),
+            Brightness::On(8)
+        );
+
+        // tomorrow's sunrise, so we're in dusk (from day) then night (from dusk)
+        let tomorrow = DaylightResponse {
+            sunrise: response().sunrise + Duration::days(1),
+            sunset: response().sunset + Duration::days(1),
+            civil_twilight_begin: response().civil_twilight_begin + Duration::days(1),
+            civil_twilight_end: response().civil_twilight_end + Duration::days(1),
+            ..response()
+        };
+        let night = Daylight::Sunrise { response: tomorrow };
+        assert_eq!(
+            schedule.brightness(&night, at("2024-06-02T00:35:00Z")),
+            Brightness::On(6)
+        );
+        assert_eq!(
+            schedule.brightness(&night, at("2024-06-02T01:08:00Z")),
+            Brightness::On(2)
+        );
+        assert_eq!(
+            schedule.brightness(&night, at("2024-06-02T04:00:00Z")),
+            Brightness::On(0)
+        );
+    }
+
+    #[test]
+    fn quiet_periods_wrap_past_midnight() {
+        let overnight = QuietPeriod {
+            start: time("23:00"),
+            end: time("07:00"),
+        };
+        assert!(overnight.contains(time("23:00")));
+        assert!(overnight.contains(time("02:00")));
+        assert!(!overnight.contains(time("07:00")));
+        assert!(!overnight.contains(time("12:00")));
+
+        let lunch = QuietPeriod {
+            start: time("12:00"),
+            end: time("13:00"),
+        };
+        assert!(lunch.contains(time("12:30")));
+        assert!(!lunch.contains(time("23:30")));
+    }
+
+    #[test]
+    fn quiet_beats_our_phase() {
+        let now = at("2024-06-01T12:00:00Z");
+        let local = now.with_timezone(&Local).time();
+        let schedule = BrightnessSchedule {
+            quiet: Some(QuietPeriod {
+                start: local - Duration::hours(1),
+                end: local + Duration::hours(1),
+            }),
+            ..BrightnessSchedule::default()
+        };
+        let day = Daylight::Sunset {
+            response: response(),
+        };
+
+        assert_eq!(schedule.brightness(&day, now), Brightness::Off);
+    }
+
+    #[test]
+    fn estimates_our_phase_until_we_know_it() {
+        let schedule = BrightnessSchedule {
+            night: 0x1,
+            day: 0xA,
+            ..BrightnessSchedule::default()
+        };
+        let local = |local: &str| {
+            let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+            Local
+                .from_local_datetime(&date.and_time(time(local)))
+                .unwrap()
+                .to_utc()
+        };
+
+        assert_eq!(
+            schedule.brightness(&Daylight::default(), local("03:00")),
+            Brightness::On(0x1)
+        );
+        assert_eq!(
+            schedule.brightness(&Daylight::default(), local("12:00")),
+            Brightness::On(0xA)
+        );
+        assert_eq!(estimated_phase(time("06:30")), Phase::Dawn);
+        assert_eq!(estimated_phase(time("19:59")), Phase::Dusk);
+        assert_eq!(estimated_phase(time("23:00")), Phase::Night);
+    }
+
+    /// Hands out our readings in order, failing once we run out
+    struct FakeSensor(Vec<f32>);
+
+    impl LightSensor for FakeSensor {
+        fn read_lux(&mut self) -> anyhow::Result<f32> {
+            if self.0.is_empty() {
+                return Err(anyhow!("Out of readings"));
+            }
+
+            Ok(self.0.remove(0))
+        }
+    }
+
+    /// 1 level for every 10 lux
+    fn linear() -> LightCurve {
+        LightCurve {
+            points: vec![(0.0, 0.0), (100.0, 10.0)],
+            hysteresis: 0.25,
+        }
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let curve = LightCurve::default();
+        assert_eq!(curve.level(50.0), 3.0);
+        assert_eq!(curve.level(275.0), 5.5);
+        assert_eq!(curve.level(2750.0), 11.5);
+
+        // we're clamped to our ends
+        assert_eq!(curve.level(0.0), 0.0);
+        assert_eq!(curve.level(-5.0), 0.0);
+        assert_eq!(curve.level(5000.0), 15.0);
+        assert_eq!(curve.level(100_000.0), 15.0);
+    }
+
+    #[test]
+    fn holds_our_intensity_near_a_boundary() {
+        let curve = linear();
+        assert_eq!(curve.intensity(57.4, None), 6);
+
+        // we need to get 0.5 + 0.25 levels away before we switch, either way
+        assert_eq!(curve.intensity(57.4, Some(5)), 5);
+        assert_eq!(curve.intensity(57.6, Some(5)), 6);
+        assert_eq!(curve.intensity(42.6, Some(5)), 5);
+        assert_eq!(curve.intensity(42.4, Some(5)), 4);
+    }
+
+    #[test]
+    fn follows_our_sensor() {
+        let mut ambient = AmbientBrightness {
+            sensor: Box::new(FakeSensor(vec![50.0, 56.0, 80.0])),
+            curve: linear(),
+            intensity: None,
+        };
+
+        assert_eq!(ambient.intensity(), Some(5));
+        assert_eq!(ambient.intensity(), Some(5));
+        assert_eq!(ambient.intensity(), Some(8));
+        // a failed reading keeps our last intensity
+        assert_eq!(ambient.intensity(), Some(8));
+    }
+
+    #[test]
+    fn validates_our_curve() {
+        let invalid = |points: Vec<(f32, f32)>, hysteresis: f32| {
+            LightCurve { points, hysteresis }
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(LightCurve::default().validate().is_ok());
+        assert_eq!(
+            invalid(vec![], 0.0),
+            "Our light curve needs at least one point"
+        );
+        assert_eq!(
+            invalid(vec![(10.0, 1.0), (10.0, 2.0)], 0.0),
+            "Our light curve points need to be sorted by lux"
+        );
+        assert_eq!(
+            invalid(vec![(10.0, 16.0)], 0.0),
+            "10 lux maps to 16, which is outside of our intensity levels"
+        );
+        assert_eq!(
+            invalid(vec![(10.0, -1.0)], 0.0),
+            "10 lux maps to -1, which is outside of our intensity levels"
+        );
+        assert_eq!(
+            invalid(vec![(10.0, 1.0)], -0.1),
+            "Our light hysteresis can't be negative"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use reqwest::Client;
+use std::collections::HashSet;
+
+lazy_static! {
+    /// Gives up on a calendar rather than leaving our events hanging
+    static ref CLIENT: Client = Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .expect("Our HTTP client builds");
+}
+
+mod ics;
+mod rrule;
+
+pub use rrule::RRule;
+
+/// When something happens, the way our calendar wrote it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Moment {
+    /// All day, in whatever time zone we're in
+    Date(NaiveDate),
+    /// The same wall clock time in whatever time zone we're in
+    Floating(NaiveDateTime),
+    Utc(NaiveDateTime),
+    Zoned(NaiveDateTime, Tz),
+}
+
+impl Moment {
+    /// Our wall clock time, all day moments start at midnight
+    pub fn naive(&self) -> NaiveDateTime {
+        match *self {
+            Moment::Date(date) => date.and_time(Default::default()),
+            Moment::Floating(naive) | Moment::Utc(naive) | Moment::Zoned(naive, _) => naive,
+        }
+    }
+
+    /// Another wall clock time in the same time zone as us, like the next time we repeat
+    fn with_naive(&self, naive: NaiveDateTime) -> Moment {
+        match *self {
+            Moment::Date(_) => Moment::Date(naive.date()),
+            Moment::Floating(_) => Moment::Floating(naive),
+            Moment::Utc(_) => Moment::Utc(naive),
+            Moment::Zoned(_, tz) => Moment::Zoned(naive, tz),
+        }
+    }
+
+    /// When we are, with all day and floating moments in `zone` (our system's time zone by default)
+    pub fn resolve(&self, zone: Option<Tz>) -> DateTime<Utc> {
+        match (*self, zone) {
+            (Moment::Utc(naive), _) => naive.and_utc(),
+            (Moment::Zoned(naive, tz), _) => resolve_local(&tz, naive),
+            (moment, Some(tz)) => resolve_local(&tz, moment.naive()),
+            (moment, None) => resolve_local(&Local, moment.naive()),
+        }
+    }
+
+    /// Our wall clock time as seen from `other`'s time zone, so we can compare an `UNTIL` with
+    /// the times we repeat at
+    fn naive_in(&self, other: &Moment, zone: Option<Tz>) -> NaiveDateTime {
+        match (*self, other) {
+            // the whole day counts
+            (Moment::Date(date), _) => date.and_hms_opt(23, 59, 59).expect("Our time is valid"),
+            (moment, Moment::Zoned(_, tz)) => moment.resolve(zone).with_timezone(tz).naive_local(),
+            (moment, Moment::Utc(_)) => moment.resolve(zone).naive_utc(),
+            (moment, _) => moment.naive(),
+        }
+    }
+}
+
+/// Wall clock times skipped by a DST change happen an hour later, and ones that happen twice
+/// happen the first time
+fn resolve_local<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> DateTime<Utc> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + Duration::hours(1)))
+                .earliest()
+        })
+        .map_or(naive.and_utc(), |time| time.with_timezone(&Utc))
+}
+
+/// A `VEVENT`, which might repeat
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub start: Moment,
+    pub end: Option<Moment>,
+    pub duration: Option<Duration>,
+    pub rrule: Option<RRule>,
+    /// Times we would repeat at, but don't
+    pub exdates: Vec<Moment>,
+    /// Which time of a repeating event (with our `uid`) we replace
+    pub recurrence_id: Option<Moment>,
+    pub cancelled: bool,
+}
+
+impl Event {
+    fn is_all_day(&self) -> bool {
+        matches!(self.start, Moment::Date(_))
+    }
+
+    /// How long each time we happen lasts
+    fn length(&self, zone: Option<Tz>) -> Duration {
+        match (self.end, self.duration) {
+            (Some(end), _) => end.resolve(zone) - self.start.resolve(zone),
+            (None, Some(duration)) => duration,
+            (None, None) if self.is_all_day() => Duration::days(1),
+            (None, None) => Duration::zero(),
+        }
+    }
+
+    /// The wall clock times we start at, up to `limit`
+    fn starts(&self, limit: NaiveDateTime, zone: Option<Tz>) -> Vec<Moment> {
+        let start = self.start.naive();
+        let starts = match &self.rrule {
+            Some(rrule) => {
+                let until = rrule.until.map(|until| until.naive_in(&self.start, zone));
+                rrule.expand(start, until, limit)
+            }
+            None => vec![start],
+        };
+
+        starts
+            .into_iter()
+            .map(|naive| self.start.with_naive(naive))
+            .collect()
+    }
+}
+
+/// One time an event happens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    pub summary: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub all_day: bool,
+}
+
+/// Every event from our ICS files
+#[derive(Debug, Clone, Default)]
+pub struct Calendar {
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    pub fn from_ics(ics: &str) -> anyhow::Result<Calendar> {
+        Ok(Calendar {
+            events: ics::parse(ics)?,
+        })
+    }
+
+    /// Add another calendar's events to ours
+    pub fn extend(&mut self, other: Calendar) {
+        self.events.extend(other.events);
+    }
+
+    /// Everything happening at some point from `from` to `to`, soonest first. All day and floating
+    /// events are in `zone`, our system's time zone by default.
+    pub fn occurrences(
+        &self,
+        zone: Option<Tz>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Occurrence> {
+        // the times of repeating events that were moved or cancelled
+        let replaced: HashSet<(&str, DateTime<Utc>)> = self
+            .events
+            .iter()
+            .filter_map(|event| Some((event.uid.as_deref()?, event.recurrence_id?.resolve(zone))))
+            .collect();
+        // far enough past `to` in any time zone
+        let limit = to.naive_utc() + Duration::days(1);
+
+        let mut occurrences: Vec<Occurrence> = self
+            .events
+            .iter()
+            .filter(|event| !event.cancelled)
+            .flat_map(|event| {
+                let replaced = &replaced;
+                let length = event.length(zone);
+                let exdates: Vec<DateTime<Utc>> = event
+                    .exdates
+                    .iter()
+                    .map(|exdate| exdate.resolve(zone))
+                    .collect();
+
+                event
+                    .starts(limit, zone)
+                    .into_iter()
+                    .map(move |start| start.resolve(zone))
+                    .filter(move |start| !exdates.contains(start))
+                    .filter(move |start| {
+                        // our replacements are events of their own
+                        event.recurrence_id.is_some()
+                            || !event
+                                .uid
+                                .as_deref()
+                                .is_some_and(|uid| replaced.contains(&(uid, *start)))
+                    })
+                    .map(move |start| Occurrence {
+                        summary: event.summary.clone(),
+                        start,
+                        end: start + length,
+                        all_day: event.is_all_day(),
+                    })
+            })
+            .filter(|occurrence| occurrence.end > from && occurrence.start < to)
+            .collect();
+        occurrences.sort_by_key(|occurrence| occurrence.start);
+
+        occurrences
+    }
+}
+
+/// Fetch a calendar from an http(s) or webcal URL, or read it from a `.ics` file
+pub async fn query_calendar(source: &str) -> anyhow::Result<Calendar> {
+    log::debug!("Querying Calendar: {source}");
+
+    let ics = if let Some(url) = source.strip_prefix("webcal://") {
+        // webcal is just https for calendar apps
+        CLIENT
+            .get(format!("https://{url}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?
+    } else if source.starts_with("http://") || source.starts_with("https://") {
+        CLIENT
+            .get(source)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?
+    } else {
+        tokio::fs::read_to_string(source).await?
+    };
+
+    Calendar::from_ics(&ics).map_err(|error| anyhow!("Couldn't parse {source}: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::at;
+
+    const NEW_YORK: Option<Tz> = Some(chrono_tz::America::New_York);
+
+    /// Our occurrences' summaries and starts in the first week of June 2024
+    fn june(ics: &str) -> Vec<(String, String)> {
+        let calendar = Calendar::from_ics(ics).unwrap();
+        calendar
+            .occurrences(
+                NEW_YORK,
+                at("2024-06-01T00:00:00Z"),
+                at("2024-06-08T00:00:00Z"),
+            )
+            .into_iter()
+            .map(|occurrence| (occurrence.summary, occurrence.start.to_rfc3339()))
+            .collect()
+    }
+
+    fn calendar(events: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{events}END:VCALENDAR\r\n")
+    }
+
+    #[test]
+    fn repeats_in_its_time_zone() {
+        // daily at 9:30 in New York, except Tuesday, with Thursday's moved to the afternoon
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             UID:standup\r\n\
+             SUMMARY:Standup\r\n\
+             DTSTART;TZID=America/New_York:20240101T093000\r\n\
+             DURATION:PT15M\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n\
+             EXDATE;TZID=America/New_York:20240604T093000\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:standup\r\n\
+             SUMMARY:Standup (moved)\r\n\
+             RECURRENCE-ID;TZID=America/New_York:20240606T093000\r\n\
+             DTSTART;TZID=America/New_York:20240606T150000\r\n\
+             DTEND;TZID=America/New_York:20240606T151500\r\n\
+             END:VEVENT\r\n",
+        );
+
+        assert_eq!(
+            june(&ics),
+            [
+                ("Standup", "2024-06-03T13:30:00+00:00"),
+                ("Standup", "2024-06-05T13:30:00+00:00"),
+                ("Standup (moved)", "2024-06-06T19:00:00+00:00"),
+                ("Standup", "2024-06-07T13:30:00+00:00"),
+            ]
+            .map(|(summary, start)| (summary.to_string(), start.to_string()))
+        );
+    }
+
+    #[test]
+    fn all_day_events_are_in_our_time_zone() {
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             SUMMARY:Holiday\r\n\
+             DTSTART;VALUE=DATE:20240604\r\n\
+             END:VEVENT\r\n",
+        );
+        let calendar = Calendar::from_ics(&ics).unwrap();
+        let occurrences = calendar.occurrences(
+            NEW_YORK,
+            at("2024-06-01T00:00:00Z"),
+            at("2024-06-08T00:00:00Z"),
+        );
+
+        assert_eq!(occurrences.len(), 1);
+        assert!(occurrences[0].all_day);
+        assert_eq!(occurrences[0].start, at("2024-06-04T04:00:00Z"));
+        assert_eq!(occurrences[0].end, at("2024-06-05T04:00:00Z"));
+    }
+
+    #[test]
+    fn keeps_its_wall_clock_time_across_dst() {
+        let ics = calendar(
+            "BEGIN:VEVENT\r\n\
+             SUMMARY:Monthly\r\n\
+             DTSTART;TZID=America/New_York:20240201T090000\r\n\
+             RRULE:FREQ=MONTHLY;BYDAY=1TH;COUNT=3\r\n\
+             END:VEVENT\r\n",
+        );
+        let calendar = Calendar::from_ics(&ics).unwrap();
+        let starts: Vec<DateTime<Utc>> = calendar
+            .occurrences(
+                NEW_YORK,
+                at("2024-01-01T00:00:00Z"),
+                at("2025-01-01T00:00:00Z"),
+            )
+            .into_iter()
+            .map(|occurrence| occurrence.start)
+            .collect();
+
+        // first Thursdays, before and after we spring forward, then we stop counting
+        assert_eq!(
+            starts,
+            [
+                at("2024-02-01T14:00:00Z"),
+                at("2024-03-07T14:00:00Z"),
+                at("2024-04-04T13:00:00Z"),
+            ]
+        );
+    }
+}
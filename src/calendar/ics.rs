@@ -0,0 +1,255 @@
+//! The parts of iCalendar we need for events, see https://datatracker.ietf.org/doc/html/rfc5545
+
+use crate::calendar::{Event, Moment, RRule};
+
+use anyhow::{anyhow, Context};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+
+/// A content line like `DTSTART;TZID=America/New_York:20240601T090000`
+#[derive(Debug)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub fn parse(ics: &str) -> anyhow::Result<Vec<Event>> {
+    let mut events = vec![];
+    // the properties of the event we're in, if we're in one
+    let mut event: Option<Vec<Property>> = None;
+    // how many components (like alarms) we're in inside of our event
+    let mut nested = 0;
+
+    for (line_number, line) in unfold(ics).iter().enumerate() {
+        let result = parse_line(line).and_then(|property| {
+            match (property.name.as_str(), property.value.as_str(), &mut event) {
+                ("BEGIN", "VEVENT", None) => event = Some(vec![]),
+                ("END", "VEVENT", Some(_)) if nested == 0 => {
+                    let properties = event.take().expect("We're in an event");
+                    events.push(parse_event(properties)?);
+                }
+                ("BEGIN", _, Some(_)) => nested += 1,
+                ("END", _, Some(_)) => nested -= 1,
+                (_, _, Some(properties)) if nested == 0 => properties.push(property),
+                _ => {}
+            }
+
+            Ok(())
+        });
+        result.with_context(|| format!("Line {}: {line}", line_number + 1))?;
+    }
+
+    Ok(events)
+}
+
+/// Long lines are folded onto lines starting with a space or tab
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => last.push_str(continued),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+fn parse_line(line: &str) -> anyhow::Result<Property> {
+    // our value starts after the first colon that isn't in a quoted parameter
+    let mut quoted = false;
+    let colon = line
+        .char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            c == ':' && !quoted
+        })
+        .map(|(i, _)| i)
+        .ok_or_else(|| anyhow!("Our line doesn't have a value"))?;
+
+    let (name_and_params, value) = (&line[..colon], &line[colon + 1..]);
+    let mut parts = name_and_params.split(';');
+    let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.trim_matches('"').to_string()))
+        .collect();
+
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+fn parse_event(properties: Vec<Property>) -> anyhow::Result<Event> {
+    let mut event = Event {
+        uid: None,
+        summary: String::new(),
+        start: Moment::Date(NaiveDate::default()),
+        end: None,
+        duration: None,
+        rrule: None,
+        exdates: vec![],
+        recurrence_id: None,
+        cancelled: false,
+    };
+    let mut start = None;
+
+    for property in properties {
+        match property.name.as_str() {
+            "UID" => event.uid = Some(property.value),
+            "SUMMARY" => event.summary = unescape(&property.value),
+            "DTSTART" => start = Some(moment(&property)?),
+            "DTEND" => event.end = Some(moment(&property)?),
+            "DURATION" => event.duration = Some(duration(&property.value)?),
+            "RRULE" => event.rrule = Some(RRule::parse(&property.value)?),
+            "EXDATE" => {
+                for value in property.value.split(',') {
+                    event
+                        .exdates
+                        .push(parse_moment(value, property.param("TZID"))?);
+                }
+            }
+            "RECURRENCE-ID" => event.recurrence_id = Some(moment(&property)?),
+            "STATUS" => event.cancelled = property.value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    event.start = start.ok_or_else(|| anyhow!("Our event {:?} has no DTSTART", event.summary))?;
+
+    Ok(event)
+}
+
+fn moment(property: &Property) -> anyhow::Result<Moment> {
+    parse_moment(&property.value, property.param("TZID"))
+}
+
+/// A `DATE` like `20240601`, or a `DATE-TIME` like `20240601T090000` in UTC (with a `Z`), `tzid`
+/// or floating
+pub fn parse_moment(value: &str, tzid: Option<&str>) -> anyhow::Result<Moment> {
+    if value.len() == 8 {
+        return Ok(Moment::Date(NaiveDate::parse_from_str(value, "%Y%m%d")?));
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        return Ok(Moment::Utc(NaiveDateTime::parse_from_str(
+            utc,
+            "%Y%m%dT%H%M%S",
+        )?));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+    Ok(match tzid {
+        Some(tzid) => match tzid.parse::<Tz>() {
+            Ok(tz) => Moment::Zoned(naive, tz),
+            Err(_) => {
+                // like Outlook's Windows time zone names, which we'd need its VTIMEZONE for
+                log::warn!("We don't know the time zone {tzid}, using our own");
+                Moment::Floating(naive)
+            }
+        },
+        None => Moment::Floating(naive),
+    })
+}
+
+/// Like `PT1H30M`, `P1D` or `-P1W`
+fn duration(value: &str) -> anyhow::Result<Duration> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value
+        .strip_prefix('P')
+        .ok_or_else(|| anyhow!("Our duration {value} doesn't start with P"))?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount = if c == 'T' { 0 } else { number.parse::<i64>()? };
+        total += match c {
+            'W' => Duration::weeks(amount),
+            'D' => Duration::days(amount),
+            'H' => Duration::hours(amount),
+            'M' => Duration::minutes(amount),
+            'S' => Duration::seconds(amount),
+            'T' => Duration::zero(),
+            c => return Err(anyhow!("Our duration has an unknown unit {c}")),
+        };
+        number.clear();
+    }
+
+    Ok(total * sign)
+}
+
+/// Text values escape their commas, semicolons, backslashes and newlines
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            // we only have 1 line
+            Some('n' | 'N') => text.push(' '),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_folded_escaped_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   SUMMARY:Lunch\\, then a very long\r\n  walk\r\n\
+                   DTSTART:20240601T160000Z\r\n\
+                   DURATION:PT1H30M\r\n\
+                   BEGIN:VALARM\r\n\
+                   DURATION:-PT15M\r\n\
+                   END:VALARM\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let events = parse(ics).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "Lunch, then a very long walk");
+        assert_eq!(
+            events[0].start,
+            Moment::Utc(NaiveDateTime::parse_from_str("20240601T160000", "%Y%m%dT%H%M%S").unwrap())
+        );
+        // not our alarm's
+        assert_eq!(events[0].duration, Some(Duration::minutes(90)));
+    }
+
+    #[test]
+    fn events_need_a_start() {
+        let ics = "BEGIN:VEVENT\r\nSUMMARY:Whenever\r\nEND:VEVENT\r\n";
+        assert!(parse(ics).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Recurrence rules like `FREQ=WEEKLY;BYDAY=MO,WE`. We support what calendar apps write for
+//! everyday events: `FREQ` (daily to yearly), `INTERVAL`, `COUNT`, `UNTIL`, `BYDAY` (with
+//! ordinals like `-1FR` in monthly and yearly rules), `BYMONTHDAY` and `BYMONTH`.
+
+use crate::calendar::ics::parse_moment;
+use crate::calendar::Moment;
+
+use anyhow::anyhow;
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, Weekday};
+
+/// Stop looking for more times after this many periods, in case a rule never matches
+const MAX_PERIODS: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    frequency: Frequency,
+    /// How many of our periods we move by each time
+    interval: u32,
+    count: Option<u32>,
+    pub until: Option<Moment>,
+    /// Weekdays, optionally only the nth (or nth from last, when negative) in our month
+    by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of our month, negative ones count back from its end
+    by_month_day: Vec<i32>,
+    by_month: Vec<u32>,
+}
+
+impl RRule {
+    pub fn parse(value: &str) -> anyhow::Result<RRule> {
+        let mut frequency = None;
+        let mut rule = RRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+        };
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Our RRULE part {part} has no value"))?;
+            let values = || value.split(',');
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        value => return Err(anyhow!("We don't support FREQ={value}")),
+                    })
+                }
+                "INTERVAL" => rule.interval = value.parse::<u32>()?.max(1),
+                "COUNT" => rule.count = Some(value.parse()?),
+                "UNTIL" => rule.until = Some(parse_moment(value, None)?),
+                "BYDAY" => rule.by_day = values().map(by_day).collect::<anyhow::Result<_>>()?,
+                "BYMONTHDAY" => {
+                    rule.by_month_day = values().map(str::parse).collect::<Result<_, _>>()?
+                }
+                "BYMONTH" => rule.by_month = values().map(str::parse).collect::<Result<_, _>>()?,
+                // like WKST, we assume weeks start on Monday
+                _ => {}
+            }
+        }
+        rule.frequency = frequency.ok_or_else(|| anyhow!("Our RRULE {value} has no FREQ"))?;
+
+        Ok(rule)
+    }
+
+    /// Every time we happen from `start` (which counts as our first) until `until` or `limit`,
+    /// whichever's first
+    pub fn expand(
+        &self,
+        start: NaiveDateTime,
+        until: Option<NaiveDateTime>,
+        limit: NaiveDateTime,
+    ) -> Vec<NaiveDateTime> {
+        let end = until.map_or(limit, |until| until.min(limit));
+        let mut times = vec![];
+
+        for period in 0..MAX_PERIODS {
+            let Some((period_start, days)) = self.period(start.date(), period * self.interval)
+            else {
+                break;
+            };
+            if period_start > end.date() {
+                break;
+            }
+
+            for day in days {
+                let time = day.and_time(start.time());
+                if time < start {
+                    continue;
+                }
+                if time > end {
+                    return times;
+                }
+
+                times.push(time);
+                if self
+                    .count
+                    .is_some_and(|count| times.len() >= count as usize)
+                {
+                    return times;
+                }
+            }
+        }
+
+        times
+    }
+
+    /// The first day of the period `offset` periods after `start`'s, and the days we happen on in
+    /// it, in order
+    fn period(&self, start: NaiveDate, offset: u32) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let (period_start, mut days) = match self.frequency {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::days(offset as i64))?;
+                let weekdays_match = self.by_day.is_empty()
+                    || self
+                        .by_day
+                        .iter()
+                        .any(|&(_, weekday)| day.weekday() == weekday);
+                let days = if weekdays_match && self.month_day_matches(day) {
+                    vec![day]
+                } else {
+                    vec![]
+                };
+                (day, days)
+            }
+            Frequency::Weekly => {
+                let monday = start.week(Weekday::Mon).first_day() + Duration::weeks(offset as i64);
+                let days = if self.by_day.is_empty() {
+                    vec![monday + Duration::days(start.weekday().num_days_from_monday() as i64)]
+                } else {
+                    let mut days: Vec<NaiveDate> = self
+                        .by_day
+                        .iter()
+                        .map(|&(_, weekday)| {
+                            monday + Duration::days(weekday.num_days_from_monday() as i64)
+                        })
+                        .collect();
+                    days.sort();
+                    days
+                };
+                (monday, days)
+            }
+            Frequency::Monthly => {
+                let month = start.with_day(1)?.checked_add_months(Months::new(offset))?;
+                (month, self.month_days(month, start.day()))
+            }
+            Frequency::Yearly => {
+                let year = start.with_day(1)?.with_month(1)?;
+                let year = year.checked_add_months(Months::new(offset * 12))?;
+                let months = if self.by_month.is_empty() {
+                    vec![start.month()]
+                } else {
+                    self.by_month.clone()
+                };
+                let mut days: Vec<NaiveDate> = months
+                    .into_iter()
+                    .filter_map(|month| year.with_month(month))
+                    .flat_map(|month| self.month_days(month, start.day()))
+                    .collect();
+                days.sort();
+                (year, days)
+            }
+        };
+
+        if !self.by_month.is_empty() {
+            days.retain(|day| self.by_month.contains(&day.month()));
+        }
+
+        Some((period_start, days))
+    }
+
+    fn month_day_matches(&self, day: NaiveDate) -> bool {
+        self.by_month_day.is_empty()
+            || self
+                .by_month_day
+                .iter()
+                .any(|&month_day| resolve_month_day(day.with_day(1), month_day) == Some(day))
+    }
+
+    /// The days in the month starting at `month` we happen on, `start_day` when we don't have any
+    /// rules about it
+    fn month_days(&self, month: NaiveDate, start_day: u32) -> Vec<NaiveDate> {
+        let by_month_day: Vec<NaiveDate> = self
+            .by_month_day
+            .iter()
+            .filter_map(|&month_day| resolve_month_day(Some(month), month_day))
+            .collect();
+        let by_day: Vec<NaiveDate> = self
+            .by_day
+            .iter()
+            .flat_map(|&(ordinal, weekday)| {
+                let matching: Vec<NaiveDate> = month
+                    .iter_days()
+                    .take_while(|day| day.month() == month.month())
+                    .filter(|day| day.weekday() == weekday)
+                    .collect();
+                match ordinal {
+                    Some(n) if n > 0 => matching.get(n as usize - 1).copied().into_iter().collect(),
+                    Some(n) => matching
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .and_then(|i| matching.get(i).copied())
+                        .into_iter()
+                        .collect(),
+                    None => matching,
+                }
+            })
+            .collect();
+
+        let mut days = match (by_month_day.is_empty(), by_day.is_empty()) {
+            (true, true) => month.with_day(start_day).into_iter().collect(),
+            (false, true) => by_month_day,
+            (true, false) => by_day,
+            (false, false) => by_day
+                .into_iter()
+                .filter(|day| by_month_day.contains(day))
+                .collect(),
+        };
+        days.sort();
+        days.dedup();
+
+        days
+    }
+}
+
+/// `month_day` of `month`, where -1 is its last day
+fn resolve_month_day(month: Option<NaiveDate>, month_day: i32) -> Option<NaiveDate> {
+    let month = month?;
+    if month_day > 0 {
+        return month.with_day(month_day as u32);
+    }
+
+    let next_month = month.checked_add_months(Months::new(1))?;
+    next_month.checked_add_signed(Duration::days(month_day as i64))
+}
+
+/// Like `MO`, `2TU` or `-1FR`
+fn by_day(value: &str) -> anyhow::Result<(Option<i32>, Weekday)> {
+    let split = value.len().saturating_sub(2);
+    let (ordinal, weekday) = value.split_at(split);
+    let weekday = match weekday {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        weekday => return Err(anyhow!("We don't know the weekday {weekday}")),
+    };
+    let ordinal = match ordinal {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse()?),
+    };
+
+    Ok((ordinal, weekday))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(rule: &str, start: &str, limit: &str) -> Vec<String> {
+        let parse = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+        RRule::parse(rule)
+            .unwrap()
+            .expand(parse(start), None, parse(limit))
+            .into_iter()
+            .map(|time| time.format("%Y-%m-%d").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_other_week() {
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH",
+                "2024-06-04 09:00",
+                "2024-06-30 00:00"
+            ),
+            ["2024-06-04", "2024-06-06", "2024-06-18", "2024-06-20"]
+        );
+    }
+
+    #[test]
+    fn last_friday_and_month_ends() {
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=-1FR;COUNT=3",
+                "2024-01-26 17:00",
+                "2025-01-01 00:00"
+            ),
+            ["2024-01-26", "2024-02-23", "2024-03-29"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                "2024-01-31 12:00",
+                "2024-04-01 00:00"
+            ),
+            ["2024-01-31", "2024-02-29", "2024-03-31"]
+        );
+        // months without a 31st are skipped
+        assert_eq!(
+            expand("FREQ=MONTHLY", "2024-01-31 12:00", "2024-06-01 00:00"),
+            ["2024-01-31", "2024-03-31", "2024-05-31"]
+        );
+    }
+
+    #[test]
+    fn yearly_until() {
+        let rule = RRule::parse("FREQ=YEARLY;UNTIL=20260101T000000Z").unwrap();
+        let start = NaiveDate::from_ymd_opt(2024, 2, 29)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let until = rule.until.unwrap().naive();
+        let limit = NaiveDate::from_ymd_opt(2030, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        // only leap years have our day
+        assert_eq!(rule.expand(start, Some(until), limit), [start]);
+    }
+}
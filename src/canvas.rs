@@ -0,0 +1,239 @@
+use crate::dot_matrix::{DotMatrix, DISPLAY_HEIGHT, ROW_LENGTH};
+
+use bitvec::vec::BitVec;
+use chrono::{DateTime, Duration, Utc};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{OriginDimensions, Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::Pixel;
+
+/// A drawing surface that can be bigger than our display (say 128x8 or 64x16), which we show part
+/// of through a `Viewport`
+///
+/// Coordinates match our DotMatrix
+/// 0,0 →  x
+/// ↓
+/// y
+pub struct Canvas {
+    size: Size,
+    pixels: BitVec,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            size: Size::new(width as u32, height as u32),
+            pixels: BitVec::repeat(false, width * height),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.size.width as usize
+    }
+
+    pub fn height(&self) -> usize {
+        self.size.height as usize
+    }
+
+    /// Anything off of our canvas is unlit
+    pub fn get(&self, x: i32, y: i32) -> bool {
+        self.offset(x, y).is_some_and(|offset| self.pixels[offset])
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, lit: bool) {
+        if let Some(offset) = self.offset(x, y) {
+            self.pixels.set(offset, lit);
+        }
+    }
+
+    fn offset(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && y >= 0 && (x as usize) < self.width() && (y as usize) < self.height() {
+            Some((y as usize * self.width()) + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// A viewport the size of our dot matrix, starting at our top left
+    pub fn viewport(&self) -> Viewport {
+        Viewport::new(
+            self.size,
+            Size::new(ROW_LENGTH as u32, DISPLAY_HEIGHT as u32),
+        )
+    }
+
+    /// Copy the part of us our viewport is looking at onto our matrix
+    pub fn show(&self, viewport: &Viewport, matrix: &mut DotMatrix) {
+        let position = viewport.position();
+        for y in 0..(viewport.size.height as i32).min(DISPLAY_HEIGHT as i32) {
+            for x in 0..(viewport.size.width as i32).min(ROW_LENGTH as i32) {
+                matrix.set_bit(
+                    x as usize,
+                    y as usize,
+                    self.get(position.x + x, position.y + y),
+                );
+            }
+        }
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+
+    type Error = anyhow::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels.into_iter() {
+            self.set(point.x, point.y, color == BinaryColor::On);
+        }
+
+        Ok(())
+    }
+}
+
+/// Which part of a canvas we show. We're clamped so we never scroll past its edges, and a canvas
+/// smaller than us stays pinned to our top left.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    position: Point,
+    size: Size,
+    canvas: Size,
+}
+
+impl Viewport {
+    pub fn new(canvas: Size, size: Size) -> Viewport {
+        Viewport {
+            position: Point::zero(),
+            size,
+            canvas,
+        }
+    }
+
+    /// Our top left corner on our canvas
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    /// The furthest we can scroll in each direction
+    pub fn max_position(&self) -> Point {
+        Point::new(
+            self.canvas.width.saturating_sub(self.size.width) as i32,
+            self.canvas.height.saturating_sub(self.size.height) as i32,
+        )
+    }
+
+    pub fn scroll_to(&mut self, position: Point) {
+        let max = self.max_position();
+        self.position = Point::new(position.x.clamp(0, max.x), position.y.clamp(0, max.y));
+    }
+
+    #[cfg(test)]
+    pub fn scroll_by(&mut self, dx: i32, dy: i32) {
+        self.scroll_to(self.position + Point::new(dx, dy));
+    }
+
+    /// Whether we've scrolled as far right and down as we can
+    #[cfg(test)]
+    pub fn at_end(&self) -> bool {
+        self.position == self.max_position()
+    }
+}
+
+/// Glides a viewport between 2 positions over `duration`, easing in and out, so scenes can pan
+/// smoothly however often they're drawn
+#[derive(Clone, Copy, Debug)]
+pub struct Pan {
+    from: Point,
+    to: Point,
+    start: DateTime<Utc>,
+    duration: Duration,
+}
+
+impl Pan {
+    pub fn new(from: Point, to: Point, start: DateTime<Utc>, duration: Duration) -> Pan {
+        Pan {
+            from,
+            to,
+            start,
+            duration,
+        }
+    }
+
+    /// How far along we are from 0 to 1
+    fn progress(&self, now: DateTime<Utc>) -> f32 {
+        if self.duration <= Duration::zero() {
+            return 1.0;
+        }
+
+        let elapsed = (now - self.start).num_milliseconds() as f32;
+        (elapsed / self.duration.num_milliseconds() as f32).clamp(0.0, 1.0)
+    }
+
+    #[cfg(test)]
+    pub fn is_finished(&self, now: DateTime<Utc>) -> bool {
+        self.progress(now) >= 1.0
+    }
+
+    /// Move our viewport to where we should be at `now`
+    pub fn apply(&self, viewport: &mut Viewport, now: DateTime<Utc>) {
+        let t = self.progress(now);
+        // smoothstep
+        let eased = t * t * (3.0 - (2.0 * t));
+        let lerp = |from: i32, to: i32| from + ((to - from) as f32 * eased).round() as i32;
+
+        viewport.scroll_to(Point::new(
+            lerp(self.from.x, self.to.x),
+            lerp(self.from.y, self.to.y),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_clamps_to_our_canvas() {
+        let mut viewport = Canvas::new(128, 8).viewport();
+        viewport.scroll_by(-5, 3);
+        assert_eq!(viewport.position(), Point::new(0, 0));
+
+        viewport.scroll_by(200, 0);
+        assert_eq!(viewport.position(), Point::new(96, 0));
+        assert!(viewport.at_end());
+
+        // too small to scroll at all
+        let mut viewport = Canvas::new(16, 4).viewport();
+        viewport.scroll_by(10, 10);
+        assert_eq!(viewport.position(), Point::zero());
+    }
+
+    #[test]
+    fn pan_eases_between_positions() {
+        let start = Utc::now();
+        let pan = Pan::new(
+            Point::zero(),
+            Point::new(96, 8),
+            start,
+            Duration::seconds(4),
+        );
+        let mut viewport = Canvas::new(128, 16).viewport();
+
+        pan.apply(&mut viewport, start + Duration::seconds(2));
+        assert_eq!(viewport.position(), Point::new(48, 4));
+        assert!(!pan.is_finished(start + Duration::seconds(2)));
+
+        pan.apply(&mut viewport, start + Duration::seconds(10));
+        assert_eq!(viewport.position(), Point::new(96, 8));
+        assert!(pan.is_finished(start + Duration::seconds(10)));
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+
+/// How much faster than real time a warped clock runs, we cycle through these
+const TIME_WARPS: [u32; 4] = [1, 60, 600, 3600];
+
+/// Where we get our time from, tests pin it so our frames are reproducible and the simulator can
+/// pause or warp it to try out a whole day
+#[derive(Clone, Copy, Debug)]
+pub enum Clock {
+    System,
+    #[cfg_attr(not(test), allow(dead_code))]
+    Fixed(DateTime<Utc>),
+    /// Shows `at` until we're resumed at `speed`
+    Paused {
+        at: DateTime<Utc>,
+        speed: u32,
+    },
+    /// `speed` times real time, counting from `from` since `since`
+    Warped {
+        since: DateTime<Utc>,
+        from: DateTime<Utc>,
+        speed: u32,
+    },
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Fixed(now) => *now,
+            Clock::Paused { at, .. } => *at,
+            Clock::Warped { since, from, speed } => {
+                let elapsed = Utc::now() - since;
+                *from + (elapsed * (*speed as i32))
+            }
+        }
+    }
+
+    fn speed(&self) -> u32 {
+        match self {
+            Clock::System | Clock::Fixed(_) => 1,
+            Clock::Paused { speed, .. } | Clock::Warped { speed, .. } => *speed,
+        }
+    }
+
+    /// Stop our time, or carry on from where we stopped
+    pub fn toggle_pause(&mut self) {
+        *self = match *self {
+            Clock::Paused { at, speed } => Clock::warped(at, speed),
+            clock => Clock::Paused {
+                at: clock.now(),
+                speed: clock.speed(),
+            },
+        };
+        log::info!("Clock {self:?}");
+    }
+
+    /// Run at our next speed, from now
+    pub fn warp(&mut self) {
+        let position = TIME_WARPS.iter().position(|speed| *speed == self.speed());
+        let speed = TIME_WARPS[position.map_or(0, |position| (position + 1) % TIME_WARPS.len())];
+        *self = match *self {
+            Clock::Paused { at, .. } => Clock::Paused { at, speed },
+            clock => Clock::warped(clock.now(), speed),
+        };
+        log::info!("Clock {self:?}");
+    }
+
+    fn warped(from: DateTime<Utc>, speed: u32) -> Clock {
+        Clock::Warped {
+            since: Utc::now(),
+            from,
+            speed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn pause_and_warp() {
+        let start = Utc::now() - Duration::days(1);
+        let mut clock = Clock::Paused {
+            at: start,
+            speed: 1,
+        };
+
+        clock.warp();
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.speed(), 60);
+
+        clock.toggle_pause();
+        assert!(clock.now() >= start);
+        clock.toggle_pause();
+        let paused = clock.now();
+        assert_eq!(clock.now(), paused);
+        assert_eq!(clock.speed(), 60);
+    }
+}
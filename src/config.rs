@@ -0,0 +1,85 @@
+use crate::brightness::{AmbientConfig, BrightnessSchedule};
+use crate::daylight::Location;
+use crate::display::DisplayConfig;
+use crate::mqtt::MqttConfig;
+use crate::notification::NotificationsConfig;
+use crate::remote::RemoteConfig;
+use crate::scene::SceneConfig;
+use crate::timer::TimerConfig;
+use crate::weather::WeatherConfig;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where we look up our sunrise and sunset
+    pub location: Location,
+    /// Where our weather scenes get their forecast
+    pub weather: WeatherConfig,
+    pub brightness: BrightnessSchedule,
+    /// Use a light sensor instead of the daylight phase for our brightness
+    pub ambient: Option<AmbientConfig>,
+    /// Each chain we drive, when we have none we use our command line for a single one
+    #[serde(rename = "display")]
+    pub displays: Vec<DisplayConfig>,
+    /// Countdowns our timer scenes show, and our remote control can start
+    #[serde(rename = "timer")]
+    pub timers: Vec<TimerConfig>,
+    /// Control us over HTTP
+    pub remote: Option<RemoteConfig>,
+    /// Where we keep notifications waiting to be shown
+    pub notifications: NotificationsConfig,
+    /// Take notifications from an MQTT topic
+    pub mqtt: Option<MqttConfig>,
+}
+
+impl Config {
+    /// Load our config, falling back to the defaults when we don't have a file
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Config> {
+        let config: Config = match path {
+            Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+            None => Config::default(),
+        };
+        config.brightness.validate()?;
+        config.weather.validate()?;
+        if let Some(ambient) = &config.ambient {
+            ambient.curve.validate()?;
+        }
+        for (i, display) in config.displays.iter().enumerate() {
+            display.validate()?;
+            if config.displays[..i]
+                .iter()
+                .any(|other| other.name == display.name)
+            {
+                return Err(anyhow!(
+                    "We have more than 1 display named {}",
+                    display.name
+                ));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Whether any of our displays show the weather, so we only ask for it when we need it
+    pub fn uses_weather(&self) -> bool {
+        self.displays
+            .iter()
+            .flat_map(|display| &display.scenes)
+            .any(|scene| matches!(scene, SceneConfig::Weather(_)))
+    }
+
+    /// The files (besides ourselves) we're loaded from, like our layouts
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.displays
+            .iter()
+            .flat_map(|display| &display.scenes)
+            .filter_map(|scene| scene.path())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+}
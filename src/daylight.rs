@@ -1,8 +1,57 @@
+use crate::clock::Clock;
+use crate::poller::{Polled, Poller};
 use crate::sunrise_sunset_api::query_daylight;
 use crate::DaylightResponse;
 use async_recursion::async_recursion;
 use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
 
+/// How often we check whether the sun has moved on, fetching the next day's times once it has
+const DAYLIGHT_REFRESH: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Where we look up our sunrise and sunset
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Default for Location {
+    /// Manhattan
+    fn default() -> Self {
+        Location {
+            latitude: 40.743722,
+            longitude: -73.978020,
+        }
+    }
+}
+
+/// Where we are in the day, as seen from the daylight we know about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Night,
+    /// Civil twilight before sunrise
+    Dawn,
+    Day,
+    /// Civil twilight after sunset
+    Dusk,
+}
+
+impl Phase {
+    pub fn previous(&self) -> Phase {
+        match self {
+            Phase::Night => Phase::Dusk,
+            Phase::Dawn => Phase::Night,
+            Phase::Day => Phase::Dawn,
+            Phase::Dusk => Phase::Day,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum Daylight {
     Sunrise {
         response: DaylightResponse,
@@ -27,11 +76,11 @@ impl Daylight {
     }
 
     #[async_recursion]
-    pub async fn update(self, now: DateTime<Utc>) -> Self {
+    pub async fn update(self, now: DateTime<Utc>, location: Location) -> Self {
         match self {
             Daylight::Sunrise { response } => {
                 if now >= response.sunrise {
-                    Daylight::Sunset { response }.update(now).await
+                    Daylight::Sunset { response }.update(now, location).await
                 } else {
                     // we're valid for this time
                     Daylight::Sunrise { response }
@@ -40,7 +89,7 @@ impl Daylight {
             Daylight::Sunset { response } => {
                 if now >= response.sunset {
                     Self::unknown(Some("tomorrow".to_string()))
-                        .update(now)
+                        .update(now, location)
                         .await
                 } else {
                     // we're valid for this time
@@ -53,7 +102,7 @@ impl Daylight {
                 backoff_seconds,
             } => {
                 if now >= try_again {
-                    query_or_backoff(now, date, backoff_seconds).await
+                    query_or_backoff(now, location, date, backoff_seconds).await
                 } else {
                     // keep waiting, we're not ready to retry
                     Daylight::Unknown {
@@ -77,6 +126,37 @@ impl Daylight {
             Daylight::Unknown { .. } => (0, 0),
         }
     }
+
+    /// The last sunrise or sunset and the next one we're counting down to. Like our phase, once the
+    /// sun sets we estimate it from tomorrow's.
+    pub fn span(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        match self {
+            Daylight::Sunrise { response } => {
+                Some((response.sunset - Duration::days(1), response.sunrise))
+            }
+            Daylight::Sunset { response } => Some((response.sunrise, response.sunset)),
+            Daylight::Unknown { .. } => None,
+        }
+    }
+
+    /// Our current phase and when it started. Once the sun sets we only know about tomorrow, so
+    /// yesterday's dusk is estimated from tomorrow's, which is only off by a few minutes.
+    pub fn phase(&self, now: DateTime<Utc>) -> Option<(Phase, DateTime<Utc>)> {
+        match self {
+            Daylight::Sunrise { response } => {
+                let last_dusk_end = response.civil_twilight_end - Duration::days(1);
+                if now >= response.civil_twilight_begin {
+                    Some((Phase::Dawn, response.civil_twilight_begin))
+                } else if now < last_dusk_end {
+                    Some((Phase::Dusk, response.sunset - Duration::days(1)))
+                } else {
+                    Some((Phase::Night, last_dusk_end))
+                }
+            }
+            Daylight::Sunset { response } => Some((Phase::Day, response.sunrise)),
+            Daylight::Unknown { .. } => None,
+        }
+    }
 }
 
 impl Default for Daylight {
@@ -87,13 +167,14 @@ impl Default for Daylight {
 
 async fn query_or_backoff(
     now: DateTime<Utc>,
+    location: Location,
     date: Option<String>,
     last_backoff_seconds: usize,
 ) -> Daylight {
-    match query_daylight(&date).await {
+    match query_daylight(location, &date).await {
         Ok(response) => {
             // even if we're not on sunrise, we'll figure it out in our next loop
-            Daylight::Sunrise { response }.update(now).await
+            Daylight::Sunrise { response }.update(now, location).await
         }
         Err(error) => {
             // wait for max 12 hours
@@ -110,7 +191,42 @@ async fn query_or_backoff(
     }
 }
 
-fn until(duration: Duration) -> (usize, usize) {
+/// Keeps our daylight up to date in the background, so we never wait on our sunrise API to draw
+pub struct Sun {
+    poller: Poller<Daylight>,
+}
+
+impl Sun {
+    /// Follow our `clock`, so we move through its day even when it's paused or warped
+    pub fn spawn(location: Location, clock: watch::Receiver<Clock>) -> Sun {
+        let last = Arc::new(Mutex::new(Daylight::default()));
+        let poller = Poller::spawn("our daylight".to_string(), DAYLIGHT_REFRESH, move || {
+            let (last, clock) = (last.clone(), clock.clone());
+            async move {
+                let daylight =
+                    std::mem::take(&mut *last.lock().expect("Our daylight isn't poisoned"));
+                let now = clock.borrow().now();
+                // an update that can't reach our API backs off by itself, so it never fails
+                let daylight = daylight.update(now, location).await;
+                *last.lock().expect("Our daylight isn't poisoned") = daylight.clone();
+
+                Ok(Polled {
+                    value: daylight,
+                    failing: false,
+                })
+            }
+        });
+
+        Sun { poller }
+    }
+
+    pub fn daylight(&self) -> Daylight {
+        self.poller.lock().value.clone()
+    }
+}
+
+/// Hours and minutes in `duration`, for counting down to something within a day
+pub fn until(duration: Duration) -> (usize, usize) {
     (
         (duration.num_hours() % 24) as usize,
         (duration.num_minutes() % 60) as usize,
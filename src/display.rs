@@ -0,0 +1,528 @@
+use crate::board::{Board, DisplayKind, GrayDepth};
+use crate::brightness::{Brightness, BrightnessSchedule, ManualBrightness};
+use crate::daylight::Daylight;
+use crate::input::Input;
+use crate::max7219::{Intensity, TransportConfig};
+use crate::notification::{NotificationConfig, Notifications, Priority};
+use crate::scene::{
+    Context, FlashScene, MessageScene, NotificationScene, Playlist, Scene, SceneConfig,
+};
+use crate::snapshot::Snapshot;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long we show a message over our scenes
+const MESSAGE_DURATION: chrono::Duration = chrono::Duration::seconds(10);
+
+/// How long we flash when a timer's done
+const FLASH_DURATION: chrono::Duration = chrono::Duration::seconds(5);
+
+/// One of our chains, from the `[[display]]` tables in our config
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub name: String,
+    pub transport: TransportConfig,
+    #[serde(default)]
+    pub kind: DisplayKind,
+    /// How many 8 digit modules are chained together, only used by 7-segment displays
+    #[serde(default = "default_digit_modules")]
+    pub digit_modules: usize,
+    pub grayscale: Option<GrayDepth>,
+    /// Log every write to our chain to a JSONL trace
+    pub trace: Option<PathBuf>,
+    /// Our own brightness schedule instead of the shared one
+    pub brightness: Option<BrightnessSchedule>,
+    #[serde(default = "default_scenes")]
+    pub scenes: Vec<SceneConfig>,
+    /// Move on to our next scene this often, otherwise we only move on when we're told to
+    pub scene_seconds: Option<u64>,
+}
+
+impl DisplayConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(brightness) = &self.brightness {
+            brightness.validate()?;
+        }
+        if self.scene_seconds == Some(0) {
+            return Err(anyhow!(
+                "Our display {}'s scene_seconds needs to be at least 1",
+                self.name
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Our own brightness schedule, or `shared` when we don't have one
+    pub fn schedule(&self, shared: &BrightnessSchedule) -> BrightnessSchedule {
+        self.brightness.clone().unwrap_or_else(|| shared.clone())
+    }
+
+    /// How long we show each of our scenes, if we move on by ourselves
+    pub fn rotation(&self) -> Option<Duration> {
+        self.scene_seconds.map(Duration::from_secs)
+    }
+
+    /// Whether we'd open the same chain as `other`, so we can change everything else live
+    pub fn same_chain(&self, other: &DisplayConfig) -> bool {
+        self.transport == other.transport
+            && self.kind == other.kind
+            && self.digit_modules == other.digit_modules
+            && self.grayscale == other.grayscale
+            && self.trace == other.trace
+    }
+}
+
+fn default_digit_modules() -> usize {
+    1
+}
+
+fn default_scenes() -> Vec<SceneConfig> {
+    vec![SceneConfig::Daylight]
+}
+
+/// Something over our scenes, until it's done
+enum Overlay {
+    /// A message or flash, for a while
+    Timed {
+        scene: Box<dyn Scene>,
+        until: DateTime<Utc>,
+    },
+    /// Until it's shown its text as many times as it repeats
+    Notification {
+        scene: NotificationScene,
+        notification: NotificationConfig,
+    },
+}
+
+impl Overlay {
+    fn timed(scene: Box<dyn Scene>, duration: chrono::Duration, now: DateTime<Utc>) -> Overlay {
+        Overlay::Timed {
+            scene,
+            until: now + duration,
+        }
+    }
+
+    /// Only high priority notifications interrupt us, and not if we're one too
+    fn priority(&self) -> Priority {
+        match self {
+            Overlay::Timed { .. } => Priority::High,
+            Overlay::Notification { notification, .. } => notification.priority,
+        }
+    }
+
+    fn is_done(&self, now: DateTime<Utc>) -> bool {
+        match self {
+            Overlay::Timed { until, .. } => now >= *until,
+            Overlay::Notification { scene, .. } => scene.is_done(now),
+        }
+    }
+
+    fn scene(&mut self) -> &mut dyn Scene {
+        match self {
+            Overlay::Timed { scene, .. } => scene.as_mut(),
+            Overlay::Notification { scene, .. } => scene,
+        }
+    }
+}
+
+/// A board with its own playlist and brightness
+pub struct Display {
+    pub name: String,
+    board: Board,
+    playlist: Playlist,
+    /// How long we show each scene, and since when we've shown this one (from our next update
+    /// when we've only just started)
+    rotation: Option<Duration>,
+    shown_since: Option<DateTime<Utc>>,
+    schedule: BrightnessSchedule,
+    brightness: Option<Brightness>,
+    manual: ManualBrightness,
+    /// A message, flash or notification over our scenes
+    overlay: Option<Overlay>,
+    /// A notification we cut off, which goes back in our queue
+    interrupted: Option<NotificationConfig>,
+    /// We just moved on from a scene (or overlay), so low priority notifications can go next
+    boundary: bool,
+}
+
+impl Display {
+    pub fn new(
+        name: String,
+        board: Board,
+        playlist: Playlist,
+        rotation: Option<Duration>,
+        schedule: BrightnessSchedule,
+    ) -> Display {
+        Display {
+            name,
+            board,
+            playlist,
+            rotation,
+            shown_since: None,
+            schedule,
+            brightness: None,
+            manual: ManualBrightness::default(),
+            overlay: None,
+            interrupted: None,
+            boundary: true,
+        }
+    }
+
+    /// Open a display from our config, `schedule` is used unless it has its own
+    pub fn open(config: &DisplayConfig, schedule: &BrightnessSchedule) -> anyhow::Result<Display> {
+        Display::open_with(config, Playlist::open(&config.scenes)?, schedule)
+    }
+
+    /// Open our chain to show scenes we've already opened
+    pub fn open_with(
+        config: &DisplayConfig,
+        playlist: Playlist,
+        schedule: &BrightnessSchedule,
+    ) -> anyhow::Result<Display> {
+        let board = Board::open(
+            config.kind,
+            config.digit_modules,
+            config.grayscale,
+            |chained_segments| {
+                let max = config.transport.open(chained_segments)?;
+                match &config.trace {
+                    Some(path) => max.traced(path),
+                    None => Ok(max),
+                }
+            },
+        )?;
+
+        Ok(Display::new(
+            config.name.clone(),
+            board,
+            playlist,
+            config.rotation(),
+            config.schedule(schedule),
+        ))
+    }
+
+    /// Show new scenes and follow a new schedule, without touching our chain
+    pub fn reload(
+        &mut self,
+        playlist: Playlist,
+        rotation: Option<Duration>,
+        schedule: BrightnessSchedule,
+    ) {
+        self.playlist.replace(playlist);
+        self.rotation = rotation;
+        self.schedule = schedule;
+        self.boundary = true;
+    }
+
+    pub fn is_gray(&self) -> bool {
+        self.board.is_gray()
+    }
+
+    /// Follow our schedule, or our `ambient` light when we have it, unless someone picked our
+    /// brightness by hand
+    pub fn update_brightness(
+        &mut self,
+        ambient: Option<Intensity>,
+        daylight: &Daylight,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let scheduled = match ambient {
+            Some(intensity) if !self.schedule.is_quiet(now) => Brightness::On(intensity),
+            _ => self.schedule.brightness(daylight, now),
+        };
+        let scheduled = self.manual.apply(scheduled);
+        if self.brightness != Some(scheduled) {
+            log::debug!(
+                "{} brightness {:?} -> {scheduled:?}",
+                self.name,
+                self.brightness
+            );
+            self.brightness = Some(scheduled);
+            self.board.set_brightness(scheduled)?;
+        }
+
+        Ok(())
+    }
+
+    /// Show the next notification we're waiting on, if it can go now. High priority ones
+    /// interrupt our scene (or a lower priority overlay), low priority ones wait until we move on
+    /// from our scene, which is always when we never move on by ourselves.
+    pub fn notify(
+        &mut self,
+        notifications: &mut Notifications,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        self.expire_overlay(now);
+        self.rotate(now);
+        if let Some(interrupted) = self.interrupted.take() {
+            notifications.requeue(&self.name, interrupted);
+        }
+        // we're only at a boundary the moment we cross it
+        let moves_on = self.rotation.is_some() && !self.playlist.is_single();
+        let boundary = std::mem::take(&mut self.boundary) || !moves_on;
+        let priority = match &self.overlay {
+            Some(overlay) if overlay.priority() == Priority::High => return Ok(()),
+            None if boundary => Priority::Low,
+            _ => Priority::High,
+        };
+
+        if let Some(notification) = notifications.take(&self.name, priority, now) {
+            log::info!("{} notification {:?}", self.name, notification.text);
+            let scene = NotificationScene::new(&notification)?;
+            self.show(Overlay::Notification {
+                scene,
+                notification,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Show `overlay` over our scenes, putting a notification it cuts off back in our queue to
+    /// show from the start later
+    fn show(&mut self, overlay: Overlay) {
+        if let Some(Overlay::Notification { notification, .. }) = self.overlay.replace(overlay) {
+            log::info!("{} interrupted {:?}", self.name, notification.text);
+            self.interrupted = Some(notification);
+        }
+    }
+
+    fn expire_overlay(&mut self, now: DateTime<Utc>) {
+        if self
+            .overlay
+            .as_ref()
+            .is_some_and(|overlay| overlay.is_done(now))
+        {
+            self.overlay = None;
+            self.boundary = true;
+        }
+    }
+
+    /// Move on to our next scene once we've shown this one long enough, waiting for our overlay
+    fn rotate(&mut self, now: DateTime<Utc>) {
+        let Some(rotation) = self.rotation else {
+            return;
+        };
+        if self.overlay.is_some() || self.playlist.is_single() {
+            return;
+        }
+
+        let shown_since = *self.shown_since.get_or_insert(now);
+        if (now - shown_since).to_std().unwrap_or_default() >= rotation {
+            self.playlist.next();
+            self.shown_since = Some(now);
+            self.boundary = true;
+        }
+    }
+
+    /// Draw our overlay if we have one, otherwise our current scene
+    pub fn render(&mut self, context: &Context) -> anyhow::Result<()> {
+        self.expire_overlay(context.now);
+        self.rotate(context.now);
+
+        let scene: &mut dyn Scene = match &mut self.overlay {
+            Some(overlay) => overlay.scene(),
+            None => self.playlist.current(),
+        };
+        self.board.render(scene, context)
+    }
+
+    /// Whether what we're showing is moving, so we're worth drawing more often
+    pub fn is_scrolling(&mut self, now: DateTime<Utc>) -> bool {
+        match &mut self.overlay {
+            Some(overlay) => overlay.scene().is_scrolling(now),
+            None => self.playlist.current().is_scrolling(now),
+        }
+    }
+
+    pub fn dither(&mut self) -> anyhow::Result<()> {
+        self.board.dither()
+    }
+
+    pub fn snapshot(&self) -> anyhow::Result<Snapshot> {
+        self.board.snapshot()
+    }
+
+    /// Handle everything but our clock, which all of our displays share
+    pub fn input(&mut self, input: &Input, now: DateTime<Utc>) -> anyhow::Result<()> {
+        match input {
+            Input::NextScene => {
+                self.playlist.next();
+                self.shown_since = Some(now);
+                self.boundary = true;
+            }
+            Input::PreviousScene => {
+                self.playlist.previous();
+                self.shown_since = Some(now);
+                self.boundary = true;
+            }
+            Input::BrightnessUp => self.manual.step(self.brightness, true),
+            Input::BrightnessDown => self.manual.step(self.brightness, false),
+            Input::TogglePower => self.manual.power = !self.manual.power,
+            Input::Message(text) => self.show(Overlay::timed(
+                Box::new(MessageScene::new(text.clone())?),
+                MESSAGE_DURATION,
+                now,
+            )),
+            Input::PauseClock
+            | Input::TimeWarp
+            | Input::StartTimer(_)
+            | Input::AddTimer(_)
+            | Input::CancelTimer(_)
+            | Input::Notify(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Flash over our scenes for a few seconds
+    pub fn flash(&mut self, now: DateTime<Utc>) {
+        self.show(Overlay::timed(Box::new(FlashScene), FLASH_DURATION, now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::NotificationConfig;
+    use crate::testing::{at, dot_matrix};
+
+    fn display(toml: &str) -> DisplayConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// A display on a recording chain flipping between 2 scenes
+    fn two_scenes(rotation: Option<Duration>) -> Display {
+        let (matrix, _) = dot_matrix();
+        let board = Board::DotMatrix {
+            matrix,
+            grayscale: None,
+        };
+        let playlist = Playlist::open(&[SceneConfig::Daylight, SceneConfig::Daylight]).unwrap();
+
+        Display::new(
+            "main".to_string(),
+            board,
+            playlist,
+            rotation,
+            BrightnessSchedule::default(),
+        )
+    }
+
+    fn low_priority(notifications: &mut Notifications) {
+        let notification: NotificationConfig =
+            serde_json::from_str(r#"{ "text": "Laundry's done" }"#).unwrap();
+        notifications.push(notification, vec!["main".to_string()]);
+    }
+
+    #[test]
+    fn low_priority_notifications_wait_for_our_next_scene() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut notifications = Notifications::default();
+        let mut display = two_scenes(Some(Duration::from_secs(20)));
+        // past the boundary we start at
+        display.notify(&mut notifications, now).unwrap();
+
+        low_priority(&mut notifications);
+        display
+            .notify(&mut notifications, now + chrono::Duration::seconds(19))
+            .unwrap();
+        assert!(display.overlay.is_none());
+
+        display
+            .notify(&mut notifications, now + chrono::Duration::seconds(20))
+            .unwrap();
+        assert!(display.overlay.is_some());
+    }
+
+    #[test]
+    fn interrupted_notifications_go_again() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut notifications = Notifications::default();
+        let mut display = two_scenes(None);
+        low_priority(&mut notifications);
+        display.notify(&mut notifications, now).unwrap();
+        assert_eq!(display.overlay.as_ref().unwrap().priority(), Priority::Low);
+
+        let urgent: NotificationConfig =
+            serde_json::from_str(r#"{ "text": "Door open!", "priority": "high" }"#).unwrap();
+        notifications.push(urgent, vec!["main".to_string()]);
+        display.notify(&mut notifications, now).unwrap();
+        assert_eq!(display.overlay.as_ref().unwrap().priority(), Priority::High);
+
+        // back in line behind our urgent one
+        display.notify(&mut notifications, now).unwrap();
+        let requeued = notifications.take("main", Priority::Low, now).unwrap();
+        assert_eq!(requeued.text, "Laundry's done");
+    }
+
+    #[test]
+    fn displays_that_never_move_on_show_them_right_away() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut notifications = Notifications::default();
+        let mut display = two_scenes(None);
+        display.notify(&mut notifications, now).unwrap();
+
+        low_priority(&mut notifications);
+        display.notify(&mut notifications, now).unwrap();
+        assert!(display.overlay.is_some());
+    }
+
+    #[test]
+    fn same_chain_ignores_what_we_can_change_live() {
+        let kitchen = display(
+            r#"
+            name = "kitchen"
+            transport = { type = "spi" }
+            "#,
+        );
+
+        let restyled = display(
+            r#"
+            name = "kitchen"
+            transport = { type = "spi", chip_select = 0 }
+            scenes = [{ type = "weather" }]
+            brightness = { night = 0, day = 15 }
+            "#,
+        );
+        assert!(kitchen.same_chain(&restyled));
+
+        for rewired in [
+            r#"transport = { type = "spi", chip_select = 1 }"#,
+            "transport = { type = \"spi\" }\nkind = \"seven-segment\"",
+            "transport = { type = \"spi\" }\ngrayscale = \"gray4\"",
+            "transport = { type = \"spi\" }\ntrace = \"kitchen.jsonl\"",
+        ] {
+            let rewired = display(&format!("name = \"kitchen\"\n{rewired}"));
+            assert!(!kitchen.same_chain(&rewired), "{rewired:?}");
+        }
+    }
+
+    #[test]
+    fn messages_go_away_after_a_while() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut notifications = Notifications::default();
+        let mut display = two_scenes(None);
+        display
+            .input(&Input::Message("Hello".to_string()), now)
+            .unwrap();
+
+        display
+            .notify(
+                &mut notifications,
+                now + MESSAGE_DURATION - chrono::Duration::seconds(1),
+            )
+            .unwrap();
+        assert!(display.overlay.is_some());
+        display
+            .notify(&mut notifications, now + MESSAGE_DURATION)
+            .unwrap();
+        assert!(display.overlay.is_none());
+    }
+}
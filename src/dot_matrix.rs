@@ -1,8 +1,11 @@
+use crate::brightness::Brightness;
 use crate::max7219::{
     DecodeMode, Intensity, Max7219, COMMAND_BITS, DATA_BITS, DIGITS, INSTRUCTION_BITS,
     INSTRUCTION_BYTES,
 };
+use crate::snapshot::Snapshot;
 
+use anyhow::anyhow;
 use bitvec::order::Msb0;
 use bitvec::prelude::*;
 
@@ -35,8 +38,13 @@ pub struct DotMatrix {
 }
 
 impl DotMatrix {
-    pub fn spi0(intensity: Intensity) -> anyhow::Result<DotMatrix> {
-        let mut max = Max7219::spi0(4)?;
+    pub fn new(mut max: Max7219, intensity: Intensity) -> anyhow::Result<DotMatrix> {
+        if max.chained_segments() != CHAINED_SEGMENTS {
+            return Err(anyhow!(
+                "Our dot matrix needs {CHAINED_SEGMENTS} chained segments, not {}",
+                max.chained_segments()
+            ));
+        }
 
         // Scan Limit drives how many segments are shown, show all 7 of them
         max.set_scan_limit(7)?;
@@ -72,6 +80,16 @@ impl DotMatrix {
         self.max.set_intensity(intensity)
     }
 
+    pub fn set_brightness(&mut self, brightness: Brightness) -> anyhow::Result<()> {
+        match brightness {
+            Brightness::Off => self.max.set_display_on(false),
+            Brightness::On(intensity) => {
+                self.set_intensity(intensity)?;
+                self.max.set_display_on(true)
+            }
+        }
+    }
+
     pub fn clear(&mut self) -> anyhow::Result<()> {
         for y in 0..DISPLAY_HEIGHT {
             for x_row in 0..CHAINED_SEGMENTS {
@@ -93,12 +111,12 @@ impl DotMatrix {
     //     Ok(())
     // }
 
-    // pub fn get_bit(&self, x: usize, y: usize) -> bool {
-    //     match offset(x, y) {
-    //         Some(o) => self.display_buffer[o],
-    //         None => false,
-    //     }
-    // }
+    pub fn get_bit(&self, x: usize, y: usize) -> bool {
+        match offset(x, y) {
+            Some(o) => self.display_buffer[o],
+            None => false,
+        }
+    }
 
     pub fn set_bit(&mut self, x: usize, y: usize, value: bool) {
         if let Some(o) = offset(x, y) {
@@ -114,6 +132,18 @@ impl DotMatrix {
         self.display_buffer.data[((y * CHAINED_SEGMENTS) + x_row) * 2 + 1] = data
     }
 
+    /// What our buffer currently holds, which is on the display after our next flush
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new(ROW_LENGTH, DISPLAY_HEIGHT);
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..ROW_LENGTH {
+                snapshot.set(x, y, if self.get_bit(x, y) { u8::MAX } else { 0 });
+            }
+        }
+
+        snapshot
+    }
+
     pub fn flush(&mut self) -> anyhow::Result<()> {
         for y in 0..DIGITS.len() {
             self.flush_row(y)?;
@@ -174,3 +204,33 @@ fn offset(x: usize, y: usize) -> Option<usize> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_golden, dot_matrix};
+
+    #[test]
+    fn segment_order() {
+        let (mut matrix, transport) = dot_matrix();
+
+        // a border and a different number of dots in each segment, so we'd notice if our chain
+        // or bit order flipped
+        for x in 0..ROW_LENGTH {
+            matrix.set_bit(x, 0, true);
+            matrix.set_bit(x, DISPLAY_HEIGHT - 1, true);
+        }
+        for y in 0..DISPLAY_HEIGHT {
+            matrix.set_bit(0, y, true);
+            matrix.set_bit(ROW_LENGTH - 1, y, true);
+        }
+        for segment in 0..CHAINED_SEGMENTS {
+            for dot in 0..=segment {
+                matrix.set_bit((segment * DISPLAY_WIDTH) + 2 + dot, 3, true);
+            }
+        }
+        matrix.flush().unwrap();
+
+        assert_golden("dot_matrix_segment_order", &transport.last_frame());
+    }
+}
@@ -0,0 +1,341 @@
+use anyhow::anyhow;
+use bitvec::vec::BitVec;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::PixelColor;
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::text::renderer::{CharacterStyle, TextMetrics, TextRenderer};
+use embedded_graphics::text::Baseline;
+use embedded_graphics::Pixel;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+mod bdf;
+mod psf;
+
+lazy_static! {
+    /// Capitals, digits and punctuation 5 pixels tall
+    pub static ref TINY_3X5: BitmapFont = builtin(include_str!("../fonts/tiny-3x5.bdf"));
+    /// Capitals, digits and punctuation 7 pixels tall, with a narrow colon for our clocks
+    pub static ref NARROW_4X7: BitmapFont = builtin(include_str!("../fonts/narrow-4x7.bdf"));
+    /// Every ASCII character in 8 pixels (with a 1 pixel descent), only as wide as it needs to be
+    pub static ref PROPORTIONAL_8: BitmapFont = builtin(include_str!("../fonts/proportional-8.bdf"));
+    /// Fonts we've loaded from files, so reloading our layouts doesn't leak another copy each time.
+    /// A font that's changed since we loaded it is loaded again.
+    static ref LOADED: Mutex<HashMap<(PathBuf, Option<SystemTime>), &'static BitmapFont>> =
+        Mutex::new(HashMap::new());
+}
+
+fn builtin(bdf: &str) -> BitmapFont {
+    BitmapFont::from_bdf(bdf).expect("Our builtin fonts parse")
+}
+
+/// A bitmap font where every glyph can have its own width, like a BDF or PSF file
+#[derive(Debug)]
+pub struct BitmapFont {
+    glyphs: HashMap<char, Glyph>,
+    /// What we show for characters we don't have
+    default_char: Option<char>,
+    /// Pixels above our baseline
+    ascent: u32,
+    /// Pixels below our baseline
+    descent: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    /// How far we move right after drawing this glyph
+    advance: u32,
+    /// Where our pixels are, relative to the start of our baseline
+    bounds: Rectangle,
+    /// Row by row, `bounds.size.width` wide
+    pixels: BitVec,
+}
+
+impl BitmapFont {
+    pub fn new(
+        glyphs: HashMap<char, Glyph>,
+        default_char: Option<char>,
+        ascent: u32,
+        descent: u32,
+    ) -> anyhow::Result<BitmapFont> {
+        if glyphs.is_empty() {
+            return Err(anyhow!("Our font doesn't have any glyphs"));
+        }
+
+        Ok(BitmapFont {
+            glyphs,
+            default_char: default_char.or(Some('?')),
+            ascent,
+            descent,
+        })
+    }
+
+    /// One of our builtin fonts by name, or a BDF or PSF file
+    pub fn open(name: &str) -> anyhow::Result<&'static BitmapFont> {
+        match name {
+            "tiny-3x5" => Ok(&TINY_3X5),
+            "narrow-4x7" => Ok(&NARROW_4X7),
+            "proportional-8" => Ok(&PROPORTIONAL_8),
+            path => {
+                let path = fs::canonicalize(path)
+                    .map_err(|error| anyhow!("Couldn't find our font {path}: {error}"))?;
+                let modified = fs::metadata(&path)?.modified().ok();
+
+                let mut loaded = LOADED.lock().unwrap();
+                if let Some(font) = loaded.get(&(path.clone(), modified)) {
+                    return Ok(font);
+                }
+                let font: &'static BitmapFont = Box::leak(Box::new(BitmapFont::load(&path)?));
+                loaded.insert((path, modified), font);
+
+                Ok(font)
+            }
+        }
+    }
+
+    /// Load a BDF or PSF (1 or 2) font, picking the format from our file's contents
+    pub fn load(path: &Path) -> anyhow::Result<BitmapFont> {
+        let bytes = fs::read(path)?;
+        let font = if bytes.starts_with(b"STARTFONT") {
+            BitmapFont::from_bdf(std::str::from_utf8(&bytes)?)
+        } else {
+            BitmapFont::from_psf(&bytes)
+        };
+
+        font.map_err(|error| anyhow!("Couldn't load our font from {path:?}: {error}"))
+    }
+
+    pub fn from_bdf(bdf: &str) -> anyhow::Result<BitmapFont> {
+        bdf::parse(bdf)
+    }
+
+    pub fn from_psf(psf: &[u8]) -> anyhow::Result<BitmapFont> {
+        psf::parse(psf)
+    }
+
+    /// Our glyph for `c`, or our default glyph if we don't have one
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&self.default_char?))
+    }
+
+    pub fn height(&self) -> u32 {
+        self.ascent + self.descent
+    }
+
+    /// How many pixels wide `text` is, including the space after our last character
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars()
+            .filter_map(|c| self.glyph(c))
+            .map(|glyph| glyph.advance)
+            .sum()
+    }
+
+    /// How many pixels wide `text` is, up to the right edge of our last character
+    pub fn tight_width(&self, text: &str) -> u32 {
+        let last = text.chars().last().and_then(|c| self.glyph(c));
+        let last_width = last.map_or(0, |glyph| {
+            (glyph.bounds.top_left.x + glyph.bounds.size.width as i32).max(0) as u32
+        });
+
+        self.text_width(text) - last.map_or(0, |glyph| glyph.advance) + last_width
+    }
+}
+
+impl Glyph {
+    /// `pixels` are row by row, `bounds.size.width` wide
+    pub fn new(advance: u32, bounds: Rectangle, pixels: BitVec) -> anyhow::Result<Glyph> {
+        if pixels.len() != bounds.size.width as usize * bounds.size.height as usize {
+            return Err(anyhow!(
+                "Our glyph has {} pixels, not {}x{}",
+                pixels.len(),
+                bounds.size.width,
+                bounds.size.height
+            ));
+        }
+
+        Ok(Glyph {
+            advance,
+            bounds,
+            pixels,
+        })
+    }
+
+    /// Our lit pixels, relative to the start of our baseline
+    fn lit(&self) -> impl Iterator<Item = Point> + '_ {
+        let width = self.bounds.size.width as usize;
+        self.pixels
+            .iter_ones()
+            .map(move |i| self.bounds.top_left + Point::new((i % width) as i32, (i / width) as i32))
+    }
+}
+
+/// Draws text in a `BitmapFont`, like `MonoTextStyle` does for embedded-graphics' fonts
+#[derive(Debug, Clone, Copy)]
+pub struct FontStyle<'a, C> {
+    pub font: &'a BitmapFont,
+    pub text_color: Option<C>,
+}
+
+impl<'a, C> FontStyle<'a, C> {
+    pub fn new(font: &'a BitmapFont, text_color: C) -> FontStyle<'a, C> {
+        FontStyle {
+            font,
+            text_color: Some(text_color),
+        }
+    }
+
+    /// How far below our `baseline` the baseline of our glyphs is
+    fn baseline_offset(&self, baseline: Baseline) -> i32 {
+        let ascent = self.font.ascent as i32;
+        match baseline {
+            Baseline::Top => ascent,
+            Baseline::Bottom => ascent - (self.font.height() as i32 - 1),
+            Baseline::Middle => ascent - (self.font.height() as i32 - 1) / 2,
+            Baseline::Alphabetic => 0,
+        }
+    }
+}
+
+impl<C: PixelColor> TextRenderer for FontStyle<'_, C> {
+    type Color = C;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let mut origin = position + Point::new(0, self.baseline_offset(baseline));
+        for glyph in text.chars().filter_map(|c| self.font.glyph(c)) {
+            if let Some(color) = self.text_color {
+                target.draw_iter(glyph.lit().map(|point| Pixel(origin + point, color)))?;
+            }
+            origin.x += glyph.advance as i32;
+        }
+
+        Ok(Point::new(origin.x, position.y))
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        _baseline: Baseline,
+        _target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        Ok(position + Point::new(width as i32, 0))
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let width = self.font.text_width(text);
+        let top = position.y + self.baseline_offset(baseline) - self.font.ascent as i32;
+
+        TextMetrics {
+            bounding_box: Rectangle::new(
+                Point::new(position.x, top),
+                Size::new(width, if width == 0 { 0 } else { self.font.height() }),
+            ),
+            next_position: position + Point::new(width as i32, 0),
+        }
+    }
+
+    fn line_height(&self) -> u32 {
+        self.font.height()
+    }
+}
+
+impl<C: PixelColor> CharacterStyle for FontStyle<'_, C> {
+    type Color = C;
+
+    fn set_text_color(&mut self, text_color: Option<Self::Color>) {
+        self.text_color = text_color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::testing::ascii_rows;
+
+    use embedded_graphics::pixelcolor::BinaryColor;
+    use embedded_graphics::text::Text;
+    use embedded_graphics::Drawable;
+
+    fn render(font: &BitmapFont, text: &str) -> String {
+        let mut canvas = Canvas::new(font.text_width(text) as usize, font.height() as usize);
+        let style = FontStyle::new(font, BinaryColor::On);
+        Text::with_baseline(text, Point::zero(), style, Baseline::Top)
+            .draw(&mut canvas)
+            .unwrap();
+
+        ascii_rows(&canvas).join("\n")
+    }
+
+    #[test]
+    fn builtin_fonts_parse() {
+        for name in ["tiny-3x5", "narrow-4x7", "proportional-8"] {
+            let font = BitmapFont::open(name).unwrap();
+            assert!((' '..='~').all(|c| font.glyphs.contains_key(&c)), "{name}");
+        }
+    }
+
+    #[test]
+    fn reopening_a_font_file_reuses_it() {
+        let path = std::env::temp_dir().join(format!("font-{}.bdf", std::process::id()));
+        fs::write(&path, include_str!("../fonts/tiny-3x5.bdf")).unwrap();
+
+        let font = BitmapFont::open(path.to_str().unwrap()).unwrap();
+        assert!(std::ptr::eq(
+            font,
+            BitmapFont::open(path.to_str().unwrap()).unwrap()
+        ));
+        assert_eq!(font.height(), TINY_3X5.height());
+
+        fs::remove_file(&path).unwrap();
+        assert!(BitmapFont::open(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn narrow_clock_fits_our_board() {
+        assert_eq!(NARROW_4X7.text_width("12:34") - 1, 21);
+        assert_eq!(
+            render(&NARROW_4X7, "1:2"),
+            [
+                "..#.....##..",
+                ".##....#..#.",
+                "..#..#....#.",
+                "..#......#..",
+                "..#..#..#...",
+                "..#....#....",
+                ".###...####.",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn proportional_glyphs_only_take_their_width() {
+        assert_eq!(PROPORTIONAL_8.text_width("ii"), 2 * 4);
+        assert_eq!(PROPORTIONAL_8.text_width("mm"), 2 * 6);
+    }
+
+    #[test]
+    fn missing_characters_use_our_default() {
+        assert_eq!(render(&TINY_3X5, "é"), render(&TINY_3X5, "?"));
+    }
+}
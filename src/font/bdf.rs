@@ -0,0 +1,175 @@
+//! The Glyph Bitmap Distribution Format, see
+//! https://adobe-type-tools.github.io/font-tech-notes/pdfs/5005.BDF_Spec.pdf
+
+use crate::font::{BitmapFont, Glyph};
+
+use anyhow::{anyhow, Context};
+use bitvec::vec::BitVec;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use std::collections::HashMap;
+
+pub fn parse(bdf: &str) -> anyhow::Result<BitmapFont> {
+    let mut lines = bdf.lines().enumerate();
+    let mut glyphs = HashMap::new();
+    let mut font_box = None;
+    let mut ascent = None;
+    let mut descent = None;
+    let mut default_char = None;
+
+    while let Some((line_number, line)) = lines.next() {
+        let (keyword, values) = split(line);
+        let result = match keyword {
+            "FONTBOUNDINGBOX" => numbers(values).map(|values| font_box = Some(values)),
+            "FONT_ASCENT" => number(values).map(|value| ascent = Some(value)),
+            "FONT_DESCENT" => number(values).map(|value| descent = Some(value)),
+            "DEFAULT_CHAR" => number(values).map(|value| default_char = char::from_u32(value)),
+            "STARTCHAR" => parse_char(&mut lines, font_box).map(|glyph| {
+                // glyphs without a unicode encoding (-1) are only reachable by name, which we don't support
+                if let Some((c, glyph)) = glyph {
+                    glyphs.insert(c, glyph);
+                }
+            }),
+            _ => Ok(()),
+        };
+        result.with_context(|| format!("Line {}: {line}", line_number + 1))?;
+    }
+
+    let font_box = font_box.ok_or_else(|| anyhow!("Our BDF is missing its FONTBOUNDINGBOX"))?;
+    // fall back to our bounding box when we don't have the (optional) ascent and descent
+    let descent = descent.unwrap_or((-font_box[3]).max(0) as u32);
+    let ascent = ascent.unwrap_or((font_box[1] + font_box[3]).max(0) as u32);
+
+    BitmapFont::new(glyphs, default_char, ascent, descent)
+}
+
+/// Everything up to our ENDCHAR
+fn parse_char<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    font_box: Option<[i32; 4]>,
+) -> anyhow::Result<Option<(char, Glyph)>> {
+    let mut encoding = None;
+    let mut advance = None;
+    let mut bounds = font_box;
+    let mut pixels = BitVec::new();
+
+    while let Some((_, line)) = lines.next() {
+        let (keyword, values) = split(line);
+        match keyword {
+            "ENCODING" => encoding = Some(numbers::<1>(values)?[0]),
+            "DWIDTH" => advance = Some(numbers::<2>(values)?[0]),
+            "BBX" => bounds = Some(numbers(values)?),
+            "BITMAP" => {
+                let [width, height, ..] = bounds.ok_or_else(|| anyhow!("Our BITMAP has no BBX"))?;
+                for _ in 0..height {
+                    let (_, row) = lines
+                        .next()
+                        .ok_or_else(|| anyhow!("Our BITMAP ends early"))?;
+                    push_row(row.trim(), width as usize, &mut pixels)?;
+                }
+            }
+            "ENDCHAR" => {
+                let [width, height, x, y] =
+                    bounds.ok_or_else(|| anyhow!("Our glyph has no BBX"))?;
+                let advance = advance
+                    .or(font_box.map(|font_box| font_box[0]))
+                    .ok_or_else(|| anyhow!("Our glyph has no DWIDTH"))?;
+                // BDF offsets are from our baseline up to our bottom left, we go down from our top left
+                let bounds = Rectangle::new(
+                    Point::new(x, -(y + height)),
+                    Size::new(width as u32, height as u32),
+                );
+                let glyph = Glyph::new(advance.max(0) as u32, bounds, pixels)?;
+
+                return Ok(encoding
+                    .and_then(|encoding| char::from_u32(u32::try_from(encoding).ok()?))
+                    .map(|c| (c, glyph)));
+            }
+            _ => (),
+        }
+    }
+
+    Err(anyhow!("Our glyph is missing its ENDCHAR"))
+}
+
+/// Each row is hex, padded out to a whole number of bytes, starting from our leftmost pixel
+fn push_row(row: &str, width: usize, pixels: &mut BitVec) -> anyhow::Result<()> {
+    let bytes = (0..row.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(row.get(i..i + 2).unwrap_or("x"), 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| anyhow!("Our bitmap row {row:?} isn't hex"))?;
+    if bytes.len() * 8 < width {
+        return Err(anyhow!("Our bitmap row {row:?} is narrower than {width}"));
+    }
+
+    pixels.extend((0..width).map(|x| bytes[x / 8] & (0x80 >> (x % 8)) != 0));
+
+    Ok(())
+}
+
+fn split(line: &str) -> (&str, &str) {
+    line.trim().split_once(' ').unwrap_or((line.trim(), ""))
+}
+
+fn number(values: &str) -> anyhow::Result<u32> {
+    Ok(values.trim().parse()?)
+}
+
+fn numbers<const N: usize>(values: &str) -> anyhow::Result<[i32; N]> {
+    let values = values
+        .split_whitespace()
+        .take(N)
+        .map(str::parse)
+        .collect::<Result<Vec<i32>, _>>()?;
+
+    values
+        .try_into()
+        .map_err(|values| anyhow!("Expected {N} numbers, not {values:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BDF: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 3 4 0 -1
+STARTPROPERTIES 1
+FONT_ASCENT 3
+ENDPROPERTIES
+CHARS 2
+STARTCHAR j
+ENCODING 106
+DWIDTH 3 0
+BBX 2 4 0 -1
+BITMAP
+40
+40
+40
+80
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BITMAP
+E0
+E0
+E0
+E0
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn parses_glyphs_below_our_baseline() {
+        let font = parse(BDF).unwrap();
+        assert_eq!((font.ascent, font.descent), (3, 1));
+        assert_eq!(font.glyphs.len(), 1);
+
+        let glyph = font.glyph('j').unwrap();
+        assert_eq!(glyph.advance, 3);
+        assert_eq!(
+            glyph.lit().collect::<Vec<_>>(),
+            [(1, -3), (1, -2), (1, -1), (0, 0)].map(Point::from)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! The Linux console's PC Screen Fonts, version 1 and 2, see
+//! https://www.win.tue.nl/~aeb/linux/kbd/font-formats-1.html
+
+use crate::font::{BitmapFont, Glyph};
+
+use anyhow::anyhow;
+use bitvec::vec::BitVec;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use std::collections::HashMap;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_MODE_HAS_SEQUENCES: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_START_SEQUENCE: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_START_SEQUENCE: u8 = 0xFE;
+
+/// Where a PSF's glyphs are and what characters they're for
+struct Layout {
+    header_size: usize,
+    glyphs: usize,
+    width: usize,
+    height: usize,
+    /// Each glyph's characters, when we don't have one each glyph is its own code point
+    table: Option<Vec<Vec<char>>>,
+}
+
+pub fn parse(psf: &[u8]) -> anyhow::Result<BitmapFont> {
+    let layout = if psf.starts_with(&PSF1_MAGIC) {
+        psf1(psf)?
+    } else if psf.starts_with(&PSF2_MAGIC) {
+        psf2(psf)?
+    } else {
+        return Err(anyhow!("We only support BDF, PSF1 and PSF2 fonts"));
+    };
+
+    if layout.width == 0 || layout.height == 0 {
+        return Err(anyhow!(
+            "Our PSF's glyphs are {}x{}, they need at least 1 pixel",
+            layout.width,
+            layout.height
+        ));
+    }
+
+    let row_bytes = layout.width.div_ceil(8);
+    let glyph_bytes = row_bytes * layout.height;
+    let bitmaps = psf
+        .get(layout.header_size..glyphs_end(layout.header_size, layout.glyphs, glyph_bytes)?)
+        .ok_or_else(|| anyhow!("Our PSF is missing some of its {} glyphs", layout.glyphs))?;
+
+    let mut glyphs = HashMap::new();
+    for (index, bitmap) in bitmaps.chunks(glyph_bytes).enumerate() {
+        let pixels: BitVec = bitmap
+            .chunks(row_bytes)
+            .flat_map(|row| (0..layout.width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0))
+            .collect();
+        // PSFs don't have a baseline, so we put ours at the bottom
+        let bounds = Rectangle::new(
+            Point::new(0, -(layout.height as i32)),
+            Size::new(layout.width as u32, layout.height as u32),
+        );
+        let glyph = Glyph::new(layout.width as u32, bounds, pixels)?;
+
+        let characters = match &layout.table {
+            Some(table) => table.get(index).cloned().unwrap_or_default(),
+            None => char::from_u32(index as u32).into_iter().collect(),
+        };
+        for c in characters {
+            glyphs.insert(c, glyph.clone());
+        }
+    }
+
+    BitmapFont::new(glyphs, None, layout.height as u32, 0)
+}
+
+fn psf1(psf: &[u8]) -> anyhow::Result<Layout> {
+    let header_size = 4;
+    let [mode, height]: [u8; 2] = psf
+        .get(2..header_size)
+        .and_then(|header| header.try_into().ok())
+        .ok_or_else(|| anyhow!("Our PSF1 header is cut short"))?;
+    let height = height as usize;
+    let glyphs = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+
+    let table = if mode & (PSF1_MODE_HAS_TABLE | PSF1_MODE_HAS_SEQUENCES) != 0 {
+        let table = &psf[glyphs_end(header_size, glyphs, height)?.min(psf.len())..];
+        let entries = table
+            .chunks_exact(2)
+            .map(|entry| u16::from_le_bytes([entry[0], entry[1]]));
+
+        let mut characters = vec![vec![]];
+        let mut in_sequence = false;
+        for entry in entries {
+            match entry {
+                PSF1_SEPARATOR => {
+                    characters.push(vec![]);
+                    in_sequence = false;
+                }
+                PSF1_START_SEQUENCE => in_sequence = true,
+                // we draw a character at a time, so we skip combining sequences
+                _ if in_sequence => (),
+                code_point => characters
+                    .last_mut()
+                    .unwrap()
+                    .extend(char::from_u32(code_point as u32)),
+            }
+        }
+        Some(characters)
+    } else {
+        None
+    };
+
+    Ok(Layout {
+        header_size,
+        glyphs,
+        width: 8,
+        height,
+        table,
+    })
+}
+
+fn psf2(psf: &[u8]) -> anyhow::Result<Layout> {
+    let header = psf
+        .get(..32)
+        .ok_or_else(|| anyhow!("Our PSF2 header is cut short"))?
+        .chunks_exact(4)
+        .map(|field| u32::from_le_bytes([field[0], field[1], field[2], field[3]]) as usize)
+        .collect::<Vec<_>>();
+    let [_magic, _version, header_size, flags, glyphs, glyph_bytes, height, width] = header[..]
+    else {
+        unreachable!("We have exactly 8 fields")
+    };
+    let expected_bytes = width.div_ceil(8).checked_mul(height);
+    if expected_bytes != Some(glyph_bytes) {
+        return Err(anyhow!(
+            "Our {width}x{height} PSF2 glyphs don't fit in {glyph_bytes} bytes each"
+        ));
+    }
+
+    let table = if flags as u32 & PSF2_HAS_UNICODE_TABLE != 0 {
+        let table = &psf[glyphs_end(header_size, glyphs, glyph_bytes)?.min(psf.len())..];
+        let characters = table
+            .split(|&byte| byte == PSF2_SEPARATOR)
+            .map(|entry| {
+                // anything after our first sequence start is a combining sequence, which we skip
+                let single = entry
+                    .split(|&byte| byte == PSF2_START_SEQUENCE)
+                    .next()
+                    .unwrap_or_default();
+                String::from_utf8_lossy(single).chars().collect()
+            })
+            .collect();
+        Some(characters)
+    } else {
+        None
+    };
+
+    Ok(Layout {
+        header_size,
+        glyphs,
+        width,
+        height,
+        table,
+    })
+}
+
+/// Where our glyphs end (and our unicode table starts), checked since it's from our header
+fn glyphs_end(header_size: usize, glyphs: usize, glyph_bytes: usize) -> anyhow::Result<usize> {
+    glyphs
+        .checked_mul(glyph_bytes)
+        .and_then(|bytes| bytes.checked_add(header_size))
+        .ok_or_else(|| anyhow!("Our PSF's {glyphs} glyphs are too big to load"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_psf2_with_a_unicode_table() {
+        let mut psf = PSF2_MAGIC.to_vec();
+        for field in [0u32, 32, PSF2_HAS_UNICODE_TABLE, 2, 3, 3, 2] {
+            psf.extend(field.to_le_bytes());
+        }
+        // a 2x3 block, and a 2x3 underline
+        psf.extend([0xC0, 0xC0, 0xC0, 0x00, 0x00, 0xC0]);
+        psf.extend("#█".as_bytes());
+        psf.push(PSF2_SEPARATOR);
+        psf.push(b'_');
+        psf.push(PSF2_SEPARATOR);
+
+        let font = parse(&psf).unwrap();
+        assert_eq!(font.height(), 3);
+        assert_eq!(font.text_width("#█_"), 6);
+        assert_eq!(
+            font.glyph('_').unwrap().lit().collect::<Vec<_>>(),
+            [(0, -1), (1, -1)].map(Point::from)
+        );
+        assert_eq!(font.glyph('█').unwrap().lit().count(), 6);
+    }
+
+    /// Just a PSF2 header, from its header size on
+    fn psf2_header(fields: [u32; 6]) -> Vec<u8> {
+        let mut psf = PSF2_MAGIC.to_vec();
+        for field in [0].into_iter().chain(fields) {
+            psf.extend(field.to_le_bytes());
+        }
+        psf
+    }
+
+    #[test]
+    fn rejects_empty_glyphs() {
+        assert!(parse(&[PSF1_MAGIC[0], PSF1_MAGIC[1], 0, 0]).is_err());
+        assert!(parse(&psf2_header([32, 0, 2, 0, 0, 8])).is_err());
+        assert!(parse(&psf2_header([32, 0, 2, 0, 8, 0])).is_err());
+    }
+
+    #[test]
+    fn rejects_glyphs_bigger_than_we_have() {
+        let most = u32::MAX;
+        assert!(parse(&psf2_header([
+            most,
+            PSF2_HAS_UNICODE_TABLE,
+            most,
+            most,
+            most,
+            8
+        ]))
+        .is_err());
+        assert!(parse(&psf2_header([32, 0, 1, 1 << 30, 1 << 30, 1 << 30])).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::dot_matrix::{DotMatrix, DISPLAY_HEIGHT, ROW_LENGTH};
+use crate::snapshot::Snapshot;
+
+use std::marker::PhantomData;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::Dimensions;
+use embedded_graphics::pixelcolor::{Gray2, Gray4, GrayColor};
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+/// Our LEDs are either on or off, so gray levels are faked by flipping through `sub_frames` binary
+/// frames quickly enough that each LED is only lit for `level / sub_frames` of the time
+/// (PWM by frame).
+///
+/// Display Coordinates match our DotMatrix
+/// 0,0 →  x
+/// ↓
+/// y
+pub struct GrayMatrix<C> {
+    levels: Vec<u8>,
+    sub_frame: u8,
+    color: PhantomData<C>,
+}
+
+impl<C: GrayColor> GrayMatrix<C> {
+    pub fn new() -> GrayMatrix<C> {
+        GrayMatrix {
+            levels: vec![0; ROW_LENGTH * DISPLAY_HEIGHT],
+            sub_frame: 0,
+            color: PhantomData,
+        }
+    }
+
+    /// How many binary frames it takes to show every gray level once
+    pub fn sub_frames() -> u8 {
+        C::WHITE.luma()
+    }
+
+    pub fn set_level(&mut self, x: usize, y: usize, level: u8) {
+        if x < ROW_LENGTH && y < DISPLAY_HEIGHT {
+            self.levels[(y * ROW_LENGTH) + x] = level.min(Self::sub_frames());
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let sub_frames = Self::sub_frames() as usize;
+        Snapshot {
+            width: ROW_LENGTH,
+            height: DISPLAY_HEIGHT,
+            pixels: self
+                .levels
+                .iter()
+                .map(|level| ((*level as usize * u8::MAX as usize) / sub_frames) as u8)
+                .collect(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.levels.fill(0);
+    }
+
+    /// Copy a binary frame over, lit pixels are fully on
+    pub fn load(&mut self, matrix: &DotMatrix) {
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..ROW_LENGTH {
+                let level = if matrix.get_bit(x, y) {
+                    Self::sub_frames()
+                } else {
+                    0
+                };
+                self.set_level(x, y, level);
+            }
+        }
+    }
+
+    /// Render our next sub frame into the matrix buffer, it still needs to be flushed
+    pub fn render_next(&mut self, matrix: &mut DotMatrix) {
+        let sub_frames = Self::sub_frames() as usize;
+        let sub_frame = self.sub_frame as usize;
+
+        for y in 0..DISPLAY_HEIGHT {
+            for x in 0..ROW_LENGTH {
+                let level = self.levels[(y * ROW_LENGTH) + x] as usize;
+                // spread our lit frames evenly across the cycle instead of lighting them all at
+                // once, which keeps the flicker rate up for dim pixels
+                let lit = ((sub_frame + 1) * level) / sub_frames > (sub_frame * level) / sub_frames;
+                matrix.set_bit(x, y, lit);
+            }
+        }
+
+        self.sub_frame = (self.sub_frame + 1) % Self::sub_frames();
+    }
+}
+
+impl<C: GrayColor> Default for GrayMatrix<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Dimensions for GrayMatrix<C> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(0, 0),
+            Size::new(ROW_LENGTH as u32, DISPLAY_HEIGHT as u32),
+        )
+    }
+}
+
+impl<C: GrayColor> DrawTarget for GrayMatrix<C> {
+    type Color = C;
+
+    type Error = anyhow::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels.into_iter() {
+            if point.x >= 0 && point.y >= 0 {
+                self.set_level(point.x as usize, point.y as usize, color.luma());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The gray depths we know how to dither
+pub enum Grayscale {
+    Gray2(GrayMatrix<Gray2>),
+    Gray4(GrayMatrix<Gray4>),
+}
+
+impl Grayscale {
+    pub fn clear(&mut self) {
+        match self {
+            Grayscale::Gray2(gray) => gray.clear(),
+            Grayscale::Gray4(gray) => gray.clear(),
+        }
+    }
+
+    pub fn load(&mut self, matrix: &DotMatrix) {
+        match self {
+            Grayscale::Gray2(gray) => gray.load(matrix),
+            Grayscale::Gray4(gray) => gray.load(matrix),
+        }
+    }
+
+    pub fn render_next(&mut self, matrix: &mut DotMatrix) {
+        match self {
+            Grayscale::Gray2(gray) => gray.render_next(matrix),
+            Grayscale::Gray4(gray) => gray.render_next(matrix),
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        match self {
+            Grayscale::Gray2(gray) => gray.snapshot(),
+            Grayscale::Gray4(gray) => gray.snapshot(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::dot_matrix;
+
+    /// Which of a full cycle's sub frames light the pixel at each level, level `n` at `(n, 0)`
+    fn cycle<C: GrayColor>() -> Vec<Vec<bool>> {
+        let sub_frames = GrayMatrix::<C>::sub_frames();
+        let mut gray = GrayMatrix::<C>::new();
+        for level in 0..=sub_frames {
+            gray.set_level(level as usize, 0, level);
+        }
+
+        let (mut matrix, _transport) = dot_matrix();
+        let mut lit = vec![vec![]; sub_frames as usize + 1];
+        for _ in 0..sub_frames {
+            gray.render_next(&mut matrix);
+            for (level, frames) in lit.iter_mut().enumerate() {
+                frames.push(matrix.get_bit(level, 0));
+            }
+        }
+
+        lit
+    }
+
+    /// Each level lights that many sub frames, spread out so the gaps between them (wrapping into
+    /// our next cycle) differ by at most 1 frame
+    fn assert_dithered(cycle: Vec<Vec<bool>>) {
+        let sub_frames = cycle.len() - 1;
+        for (level, frames) in cycle.into_iter().enumerate() {
+            let lit: Vec<usize> = (0..sub_frames).filter(|&i| frames[i]).collect();
+            assert_eq!(lit.len(), level, "level {level} lit {frames:?}");
+            if level == 0 {
+                continue;
+            }
+
+            let gaps: Vec<usize> = lit
+                .iter()
+                .zip(lit.iter().cycle().skip(1))
+                .map(|(from, to)| (to + sub_frames - from - 1) % sub_frames + 1)
+                .collect();
+            let (shortest, longest) = (gaps.iter().min().unwrap(), gaps.iter().max().unwrap());
+            assert!(longest - shortest <= 1, "level {level} lit {frames:?}");
+        }
+    }
+
+    #[test]
+    fn gray2_lights_each_level_evenly() {
+        assert_dithered(cycle::<Gray2>());
+    }
+
+    #[test]
+    fn gray4_lights_each_level_evenly() {
+        assert_dithered(cycle::<Gray4>());
+    }
+
+    #[test]
+    fn levels_are_clamped() {
+        let mut gray = GrayMatrix::<Gray2>::new();
+        gray.set_level(0, 0, 200);
+        gray.set_level(ROW_LENGTH, 0, 1);
+
+        assert_eq!(gray.levels[0], 3);
+        assert_eq!(gray.snapshot().pixels[0], u8::MAX);
+    }
+}
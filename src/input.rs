@@ -0,0 +1,30 @@
+use crate::notification::NotificationConfig;
+use crate::timer::TimerConfig;
+
+/// What someone can ask of our board, the same whether it comes from our simulator's keyboard,
+/// a button or a remote API
+#[cfg_attr(
+    not(all(feature = "max-simulator", feature = "remote")),
+    allow(dead_code)
+)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Input {
+    NextScene,
+    PreviousScene,
+    BrightnessUp,
+    BrightnessDown,
+    /// Shut our displays down, or wake them back up
+    TogglePower,
+    /// Show a message over our scenes for a little while
+    Message(String),
+    PauseClock,
+    /// Speed our clock up, cycling through a few speeds
+    TimeWarp,
+    /// Start (or restart) one of our `[[timer]]`s with `minutes`, by name
+    StartTimer(String),
+    /// Start a timer that isn't in our config
+    AddTimer(TimerConfig),
+    CancelTimer(String),
+    /// Queue a notification for all of our displays
+    Notify(NotificationConfig),
+}
@@ -0,0 +1,71 @@
+use crate::input::Input;
+use crate::Event;
+
+use ratatui::crossterm::event::KeyModifiers;
+use ratatui::crossterm::event::{self, Event as TerminalEvent, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::crossterm::terminal;
+use std::thread;
+use tokio::sync::mpsc::Sender;
+
+pub const KEY_BINDINGS: &str =
+    "←/→ scene, ↑/↓ brightness, p power, m message, space pause, w time-warp, q quit";
+
+/// Reads our simulator's keys while we're alive. This takes our terminal out of line mode, so
+/// Ctrl-C comes through as a key instead of a signal.
+pub struct Keyboard;
+
+impl Keyboard {
+    pub fn spawn(tx: &Sender<Event>) -> anyhow::Result<Keyboard> {
+        terminal::enable_raw_mode()?;
+        log::info!("Keys: {KEY_BINDINGS}");
+
+        let tx = tx.clone();
+        thread::spawn(move || loop {
+            match event::read() {
+                Ok(TerminalEvent::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if let Some(event) = binding(key) {
+                        log::debug!("{key:?} -> {event:?}");
+                        if tx.blocking_send(event).is_err() {
+                            break;
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    log::error!("Failed to read our keyboard: {error}");
+                    break;
+                }
+            }
+        });
+
+        Ok(Keyboard)
+    }
+}
+
+impl Drop for Keyboard {
+    fn drop(&mut self) {
+        if let Err(error) = terminal::disable_raw_mode() {
+            log::error!("Failed to restore our terminal: {error}");
+        }
+    }
+}
+
+fn binding(key: KeyEvent) -> Option<Event> {
+    let input = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Some(Event::Exit)
+        }
+        KeyCode::Char('q') | KeyCode::Esc => return Some(Event::Exit),
+        KeyCode::Right | KeyCode::Char('n') => Input::NextScene,
+        KeyCode::Left | KeyCode::Char('b') => Input::PreviousScene,
+        KeyCode::Up | KeyCode::Char('+') => Input::BrightnessUp,
+        KeyCode::Down | KeyCode::Char('-') => Input::BrightnessDown,
+        KeyCode::Char('p') => Input::TogglePower,
+        KeyCode::Char('m') => Input::Message("Hello World".to_string()),
+        KeyCode::Char(' ') => Input::PauseClock,
+        KeyCode::Char('w') => Input::TimeWarp,
+        _ => return None,
+    };
+
+    Some(Event::Input(input))
+}
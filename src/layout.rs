@@ -0,0 +1,418 @@
+use crate::daylight::Daylight;
+use crate::font::{BitmapFont, FontStyle};
+use crate::scene::{show_colon, Context};
+use crate::sprite::Sprite;
+
+use anyhow::anyhow;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, StyledDrawable};
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// A screen of widgets, from a TOML file like `layouts/daylight.toml`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct LayoutConfig {
+    #[serde(rename = "widget", default)]
+    widgets: Vec<WidgetConfig>,
+}
+
+/// Where a widget goes and what it shows
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct WidgetConfig {
+    content: ContentConfig,
+    /// Our box's top left
+    #[serde(default)]
+    at: [i32; 2],
+    /// Our box's width and height, by default we fit our content and padding
+    size: Option<[u32; 2]>,
+    #[serde(default)]
+    align: Align,
+    #[serde(default)]
+    valign: VerticalAlign,
+    /// Space between the edges of our box and our content
+    #[serde(default)]
+    padding: u32,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+enum ContentConfig {
+    /// A sprite by name (or path), or one chosen by our source
+    Icon {
+        sprite: Option<String>,
+        source: Option<Source>,
+    },
+    Text {
+        text: String,
+        #[serde(default = "default_font")]
+        font: String,
+    },
+    /// Hours and minutes until our source's next event
+    Countdown {
+        source: Source,
+        #[serde(default = "default_font")]
+        font: String,
+        /// Blink our colon every second
+        #[serde(default = "default_blink")]
+        blink: bool,
+    },
+    /// A bar filling our box as our source's next event gets closer
+    Progress { source: Source },
+}
+
+fn default_font() -> String {
+    "narrow-4x7".to_string()
+}
+
+fn default_blink() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Data our widgets can show
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// The next sunrise or sunset
+    Daylight,
+}
+
+impl Source {
+    /// Hours and minutes until our next event
+    fn countdown(&self, context: &Context) -> (usize, usize) {
+        match self {
+            Source::Daylight => context.daylight.until(context.now),
+        }
+    }
+
+    /// Our builtin sprite for what's next, if we know
+    fn icon(&self, context: &Context) -> Option<&'static Sprite> {
+        let name = match self {
+            Source::Daylight => match context.daylight {
+                Daylight::Sunrise { .. } => "up-arrow",
+                Daylight::Sunset { .. } => "down-arrow",
+                Daylight::Unknown { .. } => return None,
+            },
+        };
+
+        Sprite::builtin(name).ok()
+    }
+
+    /// How far we are from our last event (0) to our next one (1)
+    fn progress(&self, context: &Context) -> f32 {
+        let span = match self {
+            Source::Daylight => context.daylight.span(),
+        };
+
+        match span {
+            Some((from, to)) if to > from => {
+                let progress = (context.now - from).num_seconds() as f32
+                    / (to - from).num_seconds().max(1) as f32;
+                progress.clamp(0.0, 1.0)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Widgets placed on our display, in the order we draw them
+pub struct Layout {
+    widgets: Vec<Widget>,
+}
+
+struct Widget {
+    content: Content,
+    at: Point,
+    size: Option<Size>,
+    align: Align,
+    valign: VerticalAlign,
+    padding: u32,
+}
+
+enum Content {
+    Icon(Sprite),
+    SourceIcon(Source),
+    Text {
+        text: String,
+        font: &'static BitmapFont,
+    },
+    Countdown {
+        source: Source,
+        font: &'static BitmapFont,
+        blink: bool,
+    },
+    Progress(Source),
+}
+
+impl Layout {
+    pub fn load(path: &Path) -> anyhow::Result<Layout> {
+        Layout::from_toml(&fs::read_to_string(path)?)
+            .map_err(|error| anyhow!("Couldn't load our layout from {path:?}: {error}"))
+    }
+
+    pub fn from_toml(toml: &str) -> anyhow::Result<Layout> {
+        let config: LayoutConfig = toml::from_str(toml)?;
+
+        Ok(Layout {
+            widgets: config
+                .widgets
+                .into_iter()
+                .map(Widget::open)
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    pub fn draw<D>(&self, context: &Context, target: &mut D) -> anyhow::Result<()>
+    where
+        D: DrawTarget<Color = BinaryColor, Error = anyhow::Error>,
+    {
+        for widget in &self.widgets {
+            widget.draw(context, target)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Widget {
+    fn open(config: WidgetConfig) -> anyhow::Result<Widget> {
+        let content = match config.content {
+            ContentConfig::Icon {
+                sprite: Some(sprite),
+                source: None,
+            } => Content::Icon(Sprite::open(&sprite)?),
+            ContentConfig::Icon {
+                sprite: None,
+                source: Some(source),
+            } => Content::SourceIcon(source),
+            ContentConfig::Icon { .. } => {
+                return Err(anyhow!("Our icon needs either a sprite or a source"))
+            }
+            ContentConfig::Text { text, font } => Content::Text {
+                text,
+                font: BitmapFont::open(&font)?,
+            },
+            ContentConfig::Countdown {
+                source,
+                font,
+                blink,
+            } => Content::Countdown {
+                source,
+                font: BitmapFont::open(&font)?,
+                blink,
+            },
+            ContentConfig::Progress { source } => {
+                if config.size.is_none() {
+                    return Err(anyhow!("Our progress bar needs a size"));
+                }
+                Content::Progress(source)
+            }
+        };
+
+        Ok(Widget {
+            content,
+            at: Point::from(config.at),
+            size: config.size.map(Size::from),
+            align: config.align,
+            valign: config.valign,
+            padding: config.padding,
+        })
+    }
+
+    /// Our box, and where our content goes inside it
+    fn place(&self, content: Size) -> (Rectangle, Point) {
+        let padding = self.padding as i32;
+        let size = self
+            .size
+            .unwrap_or(content + Size::new_equal(self.padding * 2));
+        let free = Point::new(
+            size.width as i32 - content.width as i32 - padding * 2,
+            size.height as i32 - content.height as i32 - padding * 2,
+        );
+
+        let x = match self.align {
+            Align::Left => 0,
+            Align::Center => free.x / 2,
+            Align::Right => free.x,
+        };
+        let y = match self.valign {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => free.y / 2,
+            VerticalAlign::Bottom => free.y,
+        };
+
+        (
+            Rectangle::new(self.at, size),
+            self.at + Point::new(padding + x, padding + y),
+        )
+    }
+
+    fn draw<D>(&self, context: &Context, target: &mut D) -> anyhow::Result<()>
+    where
+        D: DrawTarget<Color = BinaryColor, Error = anyhow::Error>,
+    {
+        match &self.content {
+            Content::Icon(sprite) => self.draw_sprite(sprite, context, target),
+            Content::SourceIcon(source) => match source.icon(context) {
+                Some(sprite) => self.draw_sprite(sprite, context, target),
+                None => Ok(()),
+            },
+            Content::Text { text, font } => {
+                let size = Size::new(font.tight_width(text), font.height());
+                let (_, top_left) = self.place(size);
+                let style = FontStyle::new(font, BinaryColor::On);
+                Text::with_baseline(text, top_left, style, Baseline::Top).draw(target)?;
+
+                Ok(())
+            }
+            Content::Countdown {
+                source,
+                font,
+                blink,
+            } => {
+                let countdown = source.countdown(context);
+                let size = Size::new(countdown_width(font, countdown), font.height());
+                let (_, top_left) = self.place(size);
+                let colon = !blink || show_colon(context);
+                draw_countdown(font, countdown, colon, top_left, target)?;
+
+                Ok(())
+            }
+            Content::Progress(source) => {
+                let (bounds, _) = self.place(Size::zero());
+                let inner = bounds.offset(-(self.padding as i32));
+                let filled = (inner.size.width as f32 * source.progress(context)).round() as u32;
+                Rectangle::new(inner.top_left, Size::new(filled, inner.size.height))
+                    .draw_styled(&PrimitiveStyle::with_fill(BinaryColor::On), target)
+            }
+        }
+    }
+
+    fn draw_sprite<D>(
+        &self,
+        sprite: &Sprite,
+        context: &Context,
+        target: &mut D,
+    ) -> anyhow::Result<()>
+    where
+        D: DrawTarget<Color = BinaryColor, Error = anyhow::Error>,
+    {
+        let (_, top_left) = self.place(sprite.size());
+        sprite.draw(context.now, top_left, target)
+    }
+}
+
+/// How wide `HH:MM` is for our hours and minutes
+pub fn countdown_width(font: &BitmapFont, (h, m): (usize, usize)) -> u32 {
+    font.tight_width(&format!("{h:02}:{m:02}"))
+}
+
+/// Hours and minutes as `HH:MM`, like our countdown widgets show them. Scenes counting down to
+/// something else use us to look the same.
+pub fn draw_countdown<D>(
+    font: &BitmapFont,
+    (h, m): (usize, usize),
+    colon: bool,
+    top_left: Point,
+    target: &mut D,
+) -> anyhow::Result<()>
+where
+    D: DrawTarget<Color = BinaryColor, Error = anyhow::Error>,
+{
+    let style = FontStyle::new(font, BinaryColor::On);
+    let colon_at =
+        Text::with_baseline(&format!("{h:02}"), top_left, style, Baseline::Top).draw(target)?;
+    if colon {
+        Text::with_baseline(":", colon_at, style, Baseline::Top).draw(target)?;
+    }
+    let minutes_at = colon_at + Point::new(font.text_width(":") as i32, 0);
+    Text::with_baseline(&format!("{m:02}"), minutes_at, style, Baseline::Top).draw(target)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::testing::TestContext;
+    use chrono::{TimeZone, Utc};
+
+    /// Our lit pixels on an 8x8 canvas
+    fn render(toml: &str) -> Vec<(i32, i32)> {
+        let layout = Layout::from_toml(toml).unwrap();
+        let context = TestContext::new(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+        let mut canvas = Canvas::new(8, 8);
+        layout.draw(&context.context(), &mut canvas).unwrap();
+
+        (0..8)
+            .flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|&(x, y)| canvas.get(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn aligns_within_our_box() {
+        // a 1x5 full stop, lit at its bottom, in a 7x8 box with 4x1 pixels to spare
+        let dot = |align: &str, valign: &str| {
+            render(&format!(
+                r#"
+                [[widget]]
+                at = [1, 0]
+                size = [7, 8]
+                padding = 1
+                align = "{align}"
+                valign = "{valign}"
+                content = {{ type = "text", text = ".", font = "tiny-3x5" }}
+                "#
+            ))
+        };
+
+        assert_eq!(dot("left", "top"), [(2, 5)]);
+        assert_eq!(dot("center", "bottom"), [(4, 6)]);
+        assert_eq!(dot("right", "middle"), [(6, 5)]);
+    }
+
+    #[test]
+    fn icons_need_a_sprite_or_a_source() {
+        let both = r#"
+            [[widget]]
+            content = { type = "icon", sprite = "sun", source = "daylight" }
+        "#;
+        assert!(Layout::from_toml(both).is_err());
+        assert!(Layout::from_toml("[[widget]]\ncontent = { type = \"icon\" }").is_err());
+    }
+
+    #[test]
+    fn progress_bars_need_a_size() {
+        let progress = r#"
+            [[widget]]
+            content = { type = "progress", source = "daylight" }
+        "#;
+        assert!(Layout::from_toml(progress).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::fs;
+use std::io::{stdin, BufRead};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Something that can tell us how bright the room is
+pub trait LightSensor {
+    fn read_lux(&mut self) -> anyhow::Result<f32>;
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum SensorConfig {
+    /// BH1750 on I2C
+    Bh1750 {
+        #[serde(default = "SensorConfig::default_bus")]
+        bus: u8,
+        #[serde(default = "SensorConfig::default_bh1750_address")]
+        address: u16,
+    },
+    /// Read lux from a file every time we're asked, handy for faking a sensor
+    File { path: PathBuf },
+    /// Read lux, one reading per line, from stdin
+    Stdin,
+}
+
+impl SensorConfig {
+    fn default_bus() -> u8 {
+        1
+    }
+
+    fn default_bh1750_address() -> u16 {
+        0x23
+    }
+
+    pub fn open(&self) -> anyhow::Result<Box<dyn LightSensor>> {
+        match self {
+            #[cfg(feature = "max-physical")]
+            SensorConfig::Bh1750 { bus, address } => Ok(Box::new(Bh1750::new(*bus, *address)?)),
+            #[cfg(not(feature = "max-physical"))]
+            SensorConfig::Bh1750 { bus, address } => Err(anyhow!(
+                "The BH1750 on bus {bus} at {address:#04X} needs the max-physical feature"
+            )),
+            SensorConfig::File { path } => Ok(Box::new(FileSensor { path: path.clone() })),
+            SensorConfig::Stdin => Ok(Box::new(StdinSensor::open())),
+        }
+    }
+}
+
+pub struct FileSensor {
+    path: PathBuf,
+}
+
+impl LightSensor for FileSensor {
+    fn read_lux(&mut self) -> anyhow::Result<f32> {
+        Ok(fs::read_to_string(&self.path)?.trim().parse()?)
+    }
+}
+
+lazy_static! {
+    /// The last reading we were sent on stdin. We only have 1 stdin, so every `StdinSensor` (a
+    /// new one each time we reload) shares a single reader.
+    static ref STDIN_LUX: Arc<Mutex<Option<f32>>> = {
+        let lux = Arc::new(Mutex::new(None));
+
+        let reader_lux = lux.clone();
+        // reading stdin blocks, so keep it out of our async runtime
+        thread::spawn(move || {
+            for line in stdin().lock().lines() {
+                match line.map(|line| line.trim().parse::<f32>()) {
+                    Ok(Ok(lux)) => *reader_lux.lock().unwrap() = Some(lux),
+                    Ok(Err(error)) => log::warn!("Ignoring light reading: {error}"),
+                    Err(error) => {
+                        log::error!("Stopped reading light from stdin: {error}");
+                        break;
+                    }
+                }
+            }
+        });
+
+        lux
+    };
+}
+
+/// Keeps the last reading we were sent on stdin
+pub struct StdinSensor {
+    lux: Arc<Mutex<Option<f32>>>,
+}
+
+impl StdinSensor {
+    pub fn open() -> StdinSensor {
+        StdinSensor {
+            lux: STDIN_LUX.clone(),
+        }
+    }
+}
+
+impl LightSensor for StdinSensor {
+    fn read_lux(&mut self) -> anyhow::Result<f32> {
+        self.lux
+            .lock()
+            .unwrap()
+            .ok_or_else(|| anyhow!("No light reading on stdin yet"))
+    }
+}
+
+#[cfg(feature = "max-physical")]
+pub use bh1750::Bh1750;
+
+#[cfg(feature = "max-physical")]
+mod bh1750 {
+    use crate::light_sensor::LightSensor;
+    use rppal::i2c::I2c;
+
+    const POWER_ON: u8 = 0x01;
+    /// 1 lx resolution, a new measurement every 120ms
+    const CONTINUOUS_HIGH_RES_MODE: u8 = 0x10;
+    /// Counts per lux from the datasheet
+    const MEASUREMENT_ACCURACY: f32 = 1.2;
+
+    pub struct Bh1750 {
+        i2c: I2c,
+    }
+
+    impl Bh1750 {
+        pub fn new(bus: u8, address: u16) -> anyhow::Result<Bh1750> {
+            let mut i2c = I2c::with_bus(bus)?;
+            i2c.set_slave_address(address)?;
+
+            i2c.write(&[POWER_ON])?;
+            i2c.write(&[CONTINUOUS_HIGH_RES_MODE])?;
+
+            log::info!("Connected to BH1750");
+            Ok(Bh1750 { i2c })
+        }
+    }
+
+    impl LightSensor for Bh1750 {
+        fn read_lux(&mut self) -> anyhow::Result<f32> {
+            let mut buffer = [0b0; 2];
+            self.i2c.read(&mut buffer)?;
+
+            Ok(u16::from_be_bytes(buffer) as f32 / MEASUREMENT_ACCURACY)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn reads_lux_from_a_file() {
+        let path = env::temp_dir().join(format!("lux-{}.txt", std::process::id()));
+        let mut sensor = SensorConfig::File { path: path.clone() }.open().unwrap();
+        assert!(sensor.read_lux().is_err());
+
+        fs::write(&path, "123.5\n").unwrap();
+        assert_eq!(sensor.read_lux().unwrap(), 123.5);
+        fs::write(&path, "bright").unwrap();
+        assert!(sensor.read_lux().is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
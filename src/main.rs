@@ -1,42 +1,155 @@
+mod board;
+mod brightness;
+mod calendar;
+mod canvas;
+mod clock;
+mod config;
 mod daylight;
+mod display;
 mod dot_matrix;
+mod font;
+mod grayscale;
+mod gtfs_realtime;
+mod input;
+#[cfg(feature = "max-simulator")]
+mod keyboard;
+mod layout;
+mod light_sensor;
 mod max7219;
+mod mqtt;
+mod notification;
+mod open_meteo_api;
+mod poller;
+mod remote;
+mod scene;
+mod seven_segment;
+mod snapshot;
+mod sprite;
 mod sunrise_sunset_api;
+#[cfg(test)]
+mod testing;
+mod timer;
+mod watcher;
+mod weather;
 
-use crate::dot_matrix::DotMatrix;
-use tokio::sync::mpsc;
-
-use crate::daylight::Daylight;
+use crate::board::{DisplayKind, GrayDepth};
+use crate::brightness::AmbientBrightness;
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::daylight::Sun;
+use crate::display::{Display, DisplayConfig};
+use crate::input::Input;
+use crate::max7219::trace::Trace;
+use crate::max7219::{Max7219, TransportConfig};
+use crate::mqtt::Mqtt;
+use crate::notification::Notifications;
+use crate::remote::Remote;
+use crate::scene::{Context, Playlist, SceneConfig};
 use crate::sunrise_sunset_api::DaylightResponse;
-use chrono::Utc;
-use embedded_graphics::draw_target::DrawTarget;
-use embedded_graphics::geometry::Point;
-use embedded_graphics::mono_font::ascii::{FONT_4X6, FONT_5X7};
-use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::pixelcolor::BinaryColor;
-use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle, StyledDrawable};
-use embedded_graphics::text::Text;
-use embedded_graphics::Drawable;
+use crate::timer::{publish, Timer, TimerAction, Timers};
+use crate::watcher::ConfigWatcher;
+use crate::weather::{Forecast, Weather};
+use chrono::{DateTime, Utc};
+use clap::Parser;
 use env_logger::Env;
-use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::signal::ctrl_c;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 use tokio::sync::mpsc::Sender;
-use tokio::time::sleep;
+use tokio::sync::watch;
+use tokio::time::{interval, sleep, MissedTickBehavior};
+
+/// How often we show a new gray sub frame, a full Gray4 cycle is 15 of these
+const DITHER_INTERVAL: Duration = Duration::from_millis(1);
+
+/// How often we redraw while one of our displays is scrolling, rather than jumping once a second
+const SCROLL_INTERVAL: Duration = Duration::from_millis(50);
 
-lazy_static! {
-    static ref LINE_STYLE: PrimitiveStyle<BinaryColor> =
-        PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Args {
+    /// TOML config file, see config.example.toml
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// The kind of display wired to our MAX7219 chain
+    #[arg(long, value_enum, default_value_t = DisplayKind::DotMatrix)]
+    display: DisplayKind,
+    /// How many 8 digit modules are chained together, only used by 7-segment displays
+    #[arg(long, default_value_t = 1)]
+    digit_modules: usize,
+    /// Dither our dot matrix to show a few gray levels
+    #[arg(long, value_enum)]
+    grayscale: Option<GrayDepth>,
+    /// Save our first frame as a png, pbm or txt file and exit
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+    /// Where we save snapshots when we're sent SIGUSR1
+    #[arg(long, default_value = ".")]
+    snapshot_dir: PathBuf,
+    /// How many pixels (or characters) wide each LED is in our snapshots
+    #[arg(long, default_value_t = 1)]
+    snapshot_scale: usize,
+    /// Record our frames to an animated gif or png instead of driving a real display
+    #[arg(long)]
+    record: Option<PathBuf>,
+    /// How many pixels wide each LED is in our recording
+    #[arg(long, default_value_t = 8)]
+    record_scale: usize,
+    /// Stop after recording for this many seconds
+    #[arg(long)]
+    record_seconds: Option<u64>,
+    /// Log every write to our chain, with timestamps, to a JSONL trace
+    #[arg(long)]
+    trace: Option<PathBuf>,
+    /// Send a trace to our chain (or whatever stands in for it) instead of drawing, then exit
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Drive our chain through this Linux SPI device (e.g. /dev/spidev0.0) instead of the Pi's SPI0
+    #[cfg(feature = "max-spidev")]
+    #[arg(long)]
+    spidev: Option<PathBuf>,
+    /// Our SPI clock speed, only used with --spidev
+    #[cfg(feature = "max-spidev")]
+    #[arg(long, default_value_t = 10_000_000)]
+    spi_speed: u32,
+    /// Our SPI mode (0-3), only used with --spidev
+    #[cfg(feature = "max-spidev")]
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=3))]
+    spi_mode: u8,
+    /// Bit-bang our chain on these GPIO lines: DIN,CLK,CS
+    #[cfg(feature = "max-gpio")]
+    #[arg(long, value_parser = parse_gpio_pins)]
+    gpio_pins: Option<[u32; 3]>,
+    /// The GPIO chip our --gpio-pins are on
+    #[cfg(feature = "max-gpio")]
+    #[arg(long, default_value = "/dev/gpiochip0")]
+    gpio_chip: PathBuf,
+    /// Serve a web simulator on this address instead of driving a real display
+    #[cfg(feature = "max-web")]
+    #[arg(long)]
+    web: Option<std::net::SocketAddr>,
 }
 
 #[derive(Debug)]
 enum Event {
     UpdateDisplay,
+    Snapshot,
+    #[cfg_attr(
+        not(any(feature = "max-simulator", feature = "remote", feature = "mqtt")),
+        allow(dead_code)
+    )]
+    Input(Input),
+    /// Our config or layouts changed, or we were sent SIGHUP
+    Reload,
     Exit,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
     let env = Env::new().default_filter_or("info");
     #[cfg(not(feature = "max-simulator"))]
     env_logger::init_from_env(env);
@@ -49,57 +162,216 @@ async fn main() -> anyhow::Result<()> {
         tui_logger::set_default_level(filter);
     }
 
+    let mut config = Config::load(args.config.as_deref())?;
+
+    if let Some(path) = &args.replay {
+        let trace = Trace::load(path)?;
+        let mut max = open_max(&args, trace.chained_segments())?;
+        tokio::select! {
+            replayed = trace.replay(&mut max) => replayed?,
+            _ = ctrl_c() => log::info!("Exiting"),
+        }
+
+        return Ok(());
+    }
+
+    let mut displays = display_configs(&config, &args)
+        .iter()
+        .map(|display| Display::open(display, &config.brightness))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
     let (tx, mut rx) = mpsc::channel(8);
 
     spawn_sigint_listener(&tx);
+    #[cfg(feature = "max-simulator")]
+    let _keyboard = keyboard::Keyboard::spawn(&tx)?;
+    spawn_snapshot_listener(&tx)?;
+    spawn_reload_listener(&tx)?;
+    let mut _remote = config
+        .remote
+        .as_ref()
+        .map(|remote| Remote::spawn(remote, &tx))
+        .transpose()?;
+    let mut _mqtt = config
+        .mqtt
+        .as_ref()
+        .map(|mqtt| Mqtt::spawn(mqtt, &tx))
+        .transpose()?;
+    let mut watcher = ConfigWatcher::spawn(&tx)?;
+    watch(&mut watcher, &config, &args)?;
     spawn_display_updater(&tx);
+    if let Some(seconds) = args.record_seconds {
+        spawn_exit_timer(&tx, Duration::from_secs(seconds));
+    }
 
-    let mut matrix = DotMatrix::spi0(0x0)?;
-    let mut daylight = Daylight::default();
-    let mut show_colon = false;
+    let clock = watch::Sender::new(Clock::System);
+    let mut sun = Sun::spawn(config.location, clock.subscribe());
+    let mut forecast = spawn_forecast(&config);
+    let mut timers = Timers::new(&config.timers, clock.borrow().now())?;
+    let mut notifications = load_notifications(&config, clock.borrow().now());
+    notifications.retain_displays(&display_names(&displays));
+    let mut ambient = config
+        .ambient
+        .as_ref()
+        .map(AmbientBrightness::open)
+        .transpose()?;
 
-    while let Some(event) = rx.recv().await {
+    let mut ditherer = interval(DITHER_INTERVAL);
+    ditherer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut scroller = interval(SCROLL_INTERVAL);
+    scroller.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut scrolling = false;
+    loop {
+        // dithering runs off its own interval rather than our channel, so its sub frames never
+        // crowd out our other events
+        let dithering = displays.iter().any(Display::is_gray);
+        let event = tokio::select! {
+            _ = ditherer.tick(), if dithering => {
+                for display in &mut displays {
+                    display.dither()?;
+                }
+                continue;
+            }
+            _ = scroller.tick(), if scrolling => Some(Event::UpdateDisplay),
+            event = rx.recv() => event,
+        };
+        let Some(event) = event else {
+            break;
+        };
         log::trace!("{event:?}");
         match event {
             Event::UpdateDisplay => {
-                let now = Utc::now();
-                daylight = daylight.update(now).await;
-
-                matrix.clear()?;
-
-                draw_sun(5, 7, &mut matrix)?;
-
-                match daylight {
-                    Daylight::Sunrise { .. } => draw_up_arrow(5, 3, &mut matrix)?,
-                    Daylight::Sunset { .. } => draw_down_arrow(5, 3, &mut matrix)?,
-                    Daylight::Unknown { .. } => (),
+                let now = clock.borrow().now();
+                let daylight = sun.daylight();
+                let weather = forecast
+                    .as_ref()
+                    .map_or_else(Weather::default, Forecast::weather);
+                for timer in timers.update(now) {
+                    finish(&timer, &mut displays, &mut notifications, now)?;
                 }
-                let (h, m) = daylight.until(now);
 
-                Text::new(
-                    &format!("{h:02}"),
-                    Point::new(11, 6),
-                    MonoTextStyle::new(&FONT_5X7, BinaryColor::On),
-                )
-                .draw(&mut matrix)?;
-                Text::new(
-                    &format!("{m:02}"),
-                    Point::new(23, 6),
-                    MonoTextStyle::new(&FONT_5X7, BinaryColor::On),
-                )
-                .draw(&mut matrix)?;
-
-                show_colon = !show_colon;
-                if show_colon {
-                    Text::new(
-                        ":",
-                        Point::new(20, 5),
-                        MonoTextStyle::new(&FONT_4X6, BinaryColor::On),
-                    )
-                    .draw(&mut matrix)?;
+                let ambient = ambient.as_mut().and_then(AmbientBrightness::intensity);
+                let context = Context {
+                    now,
+                    daylight: &daylight,
+                    weather: &weather,
+                    timers: &timers,
+                };
+                for display in &mut displays {
+                    display.update_brightness(ambient, &daylight, now)?;
+                    display.notify(&mut notifications, now)?;
+                    display.render(&context)?;
                 }
+                scrolling = displays.iter_mut().any(|display| display.is_scrolling(now));
 
-                matrix.flush()?;
+                if let Some(path) = &args.snapshot {
+                    displays[0].snapshot()?.save(path, args.snapshot_scale)?;
+                    log::info!("Saved our snapshot to {path:?}");
+                    break;
+                }
+            }
+            Event::Snapshot => {
+                for display in &displays {
+                    if let Err(error) = save_snapshots(display, &args) {
+                        log::error!("Failed to save a snapshot of {}: {error:?}", display.name);
+                    }
+                }
+            }
+            Event::Input(input) => {
+                log::info!("{input:?}");
+                match input {
+                    Input::PauseClock => clock.send_modify(Clock::toggle_pause),
+                    Input::TimeWarp => clock.send_modify(Clock::warp),
+                    Input::StartTimer(name) => {
+                        if let Err(error) = timers.start(&name, clock.borrow().now()) {
+                            log::error!("{error}");
+                        }
+                    }
+                    Input::AddTimer(timer) => {
+                        if let Err(error) = timers.add(timer, clock.borrow().now()) {
+                            log::error!("Failed to add our timer: {error:?}");
+                        }
+                    }
+                    Input::CancelTimer(name) => {
+                        if !timers.cancel(&name) {
+                            log::warn!("We don't have a timer named {name} to cancel");
+                        }
+                    }
+                    Input::Notify(notification) => {
+                        notifications.push(notification, display_names(&displays))
+                    }
+                    input => {
+                        for display in &mut displays {
+                            display.input(&input, clock.borrow().now())?;
+                        }
+                    }
+                }
+                // show our change right away, unless we're already about to
+                let _ = tx.try_send(Event::UpdateDisplay);
+            }
+            Event::Reload => {
+                let (weather_config, remote_config, mqtt_config) = (
+                    config.weather.clone(),
+                    config.remote.clone(),
+                    config.mqtt.clone(),
+                );
+                let reloaded = reload(
+                    &args,
+                    &mut config,
+                    &mut displays,
+                    &mut ambient,
+                    &mut timers,
+                    clock.borrow().now(),
+                );
+                match reloaded {
+                    Ok(moved) => {
+                        // a new location means our daylight and weather are for somewhere else
+                        if moved {
+                            sun = Sun::spawn(config.location, clock.subscribe());
+                        }
+                        if moved
+                            || config.weather != weather_config
+                            || forecast.is_some() != config.uses_weather()
+                        {
+                            forecast = spawn_forecast(&config);
+                        }
+                        if config.remote != remote_config {
+                            // let go of our address before we listen on it again
+                            _remote = None;
+                            _remote =
+                                config.remote.as_ref().and_then(|config| {
+                                    match Remote::spawn(config, &tx) {
+                                        Ok(remote) => Some(remote),
+                                        Err(error) => {
+                                            log::error!(
+                                                "Failed to serve our remote control: {error:?}"
+                                            );
+                                            None
+                                        }
+                                    }
+                                });
+                        }
+                        if config.mqtt != mqtt_config {
+                            _mqtt = config.mqtt.as_ref().and_then(|config| {
+                                match Mqtt::spawn(config, &tx) {
+                                    Ok(mqtt) => Some(mqtt),
+                                    Err(error) => {
+                                        log::error!("Failed to listen to MQTT: {error:?}");
+                                        None
+                                    }
+                                }
+                            });
+                        }
+                        notifications.move_to(&config.notifications.path);
+                        notifications.retain_displays(&display_names(&displays));
+                        log::info!("Reloaded our config");
+                    }
+                    Err(error) => log::error!("Keeping our old config: {error:?}"),
+                }
+                if let Err(error) = watch(&mut watcher, &config, &args) {
+                    log::error!("Failed to watch our config: {error:?}");
+                }
+                let _ = tx.try_send(Event::UpdateDisplay);
             }
             Event::Exit => break,
         }
@@ -108,42 +380,259 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn draw_sun<D, E>(x: i32, y: i32, target: &mut D) -> Result<(), E>
-where
-    D: DrawTarget<Color = BinaryColor, Error = E>,
-{
-    let diameter = 5;
-    Circle::with_center(Point::new(x, y), diameter)
-        .draw_styled(&PrimitiveStyle::with_fill(BinaryColor::On), target)?;
+/// Our configured displays, or a single one from our command line when we don't have any
+fn display_configs(config: &Config, args: &Args) -> Vec<DisplayConfig> {
+    if !config.displays.is_empty() {
+        return config.displays.clone();
+    }
 
-    Line::new(Point::new(x - 5, y), Point::new(x - 4, y)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x + 5, y), Point::new(x + 4, y)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x - 4, y - 4), Point::new(x - 3, y - 3))
-        .draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x + 4, y - 4), Point::new(x + 3, y - 3))
-        .draw_styled(&LINE_STYLE, target)?;
+    vec![DisplayConfig {
+        name: "main".to_string(),
+        transport: transport_config(args),
+        kind: args.display,
+        digit_modules: args.digit_modules,
+        grayscale: args.grayscale,
+        trace: args.trace.clone(),
+        brightness: None,
+        scenes: vec![SceneConfig::Daylight],
+        scene_seconds: None,
+    }]
+}
 
-    Ok(())
+/// Keep our forecast fresh, if any of our displays show it
+fn spawn_forecast(config: &Config) -> Option<Forecast> {
+    config
+        .uses_weather()
+        .then(|| Forecast::spawn(config.location, &config.weather))
+}
+
+/// The names of the displays we're driving, which notifications are queued for
+fn display_names(displays: &[Display]) -> Vec<String> {
+    displays
+        .iter()
+        .map(|display| display.name.clone())
+        .collect()
+}
+
+/// The notifications we saved before we restarted, or none if we can't read them
+fn load_notifications(config: &Config, now: DateTime<Utc>) -> Notifications {
+    let path = &config.notifications.path;
+    Notifications::load(path, now).unwrap_or_else(|error| {
+        log::error!("Starting without our saved notifications from {path:?}: {error:?}");
+        Notifications::new(path)
+    })
 }
 
-fn draw_down_arrow<D, E>(x: i32, y: i32, target: &mut D) -> Result<(), E>
-where
-    D: DrawTarget<Color = BinaryColor, Error = E>,
-{
-    Line::new(Point::new(x, y - 3), Point::new(x, y)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x - 2, y - 2), Point::new(x, y)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x + 2, y - 2), Point::new(x, y)).draw_styled(&LINE_STYLE, target)?;
+/// Apply our config file again, leaving everything as it was if it's invalid. Displays whose chain
+/// hasn't changed keep running, the rest are reopened. Returns whether our location changed.
+fn reload(
+    args: &Args,
+    config: &mut Config,
+    displays: &mut Vec<Display>,
+    ambient: &mut Option<AmbientBrightness>,
+    timers: &mut Timers,
+    now: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    let reloaded = Config::load(args.config.as_deref())?;
+    let (old_configs, new_configs) = (
+        display_configs(config, args),
+        display_configs(&reloaded, args),
+    );
+
+    // open everything that can fail before we change anything
+    let playlists = new_configs
+        .iter()
+        .map(|display| Playlist::open(&display.scenes))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // an unchanged sensor keeps its last reading (and our stdin reader)
+    let reloaded_ambient = if reloaded.ambient != config.ambient {
+        Some(
+            reloaded
+                .ambient
+                .as_ref()
+                .map(AmbientBrightness::open)
+                .transpose()?,
+        )
+    } else {
+        None
+    };
+    let reloaded_timers = Timers::new(&reloaded.timers, now)?;
+
+    // let go of the chains we're replacing before we open them again
+    let (mut kept, replaced): (Vec<_>, Vec<_>) = displays.drain(..).partition(|display| {
+        let old = old_configs.iter().find(|old| old.name == display.name);
+        let new = new_configs.iter().find(|new| new.name == display.name);
+        old.zip(new).is_some_and(|(old, new)| old.same_chain(new))
+    });
+    let replaced = display_names(&replaced);
+
+    // open our new chains before we change the ones we kept, so a chain that won't open leaves
+    // us on our old config
+    let mut playlists = playlists.into_iter().map(Some).collect::<Vec<_>>();
+    let mut opened = Vec::new();
+    for (display, playlist) in new_configs.iter().zip(&mut playlists) {
+        if kept.iter().any(|kept| kept.name == display.name) {
+            continue;
+        }
+
+        let playlist = playlist.take().expect("We open each display once");
+        match Display::open_with(display, playlist, &reloaded.brightness) {
+            Ok(display) => opened.push(display),
+            Err(error) => {
+                drop(opened);
+                for old in &old_configs {
+                    match kept.iter().position(|kept| kept.name == old.name) {
+                        Some(i) => displays.push(kept.swap_remove(i)),
+                        None if replaced.contains(&old.name) => {
+                            match Display::open(old, &config.brightness) {
+                                Ok(reopened) => displays.push(reopened),
+                                Err(error) => {
+                                    log::error!("Failed to reopen {}: {error:?}", old.name)
+                                }
+                            }
+                        }
+                        None => (),
+                    }
+                }
+
+                return Err(error.context(format!("Couldn't open {}", display.name)));
+            }
+        }
+    }
+
+    let mut opened = opened.into_iter();
+    for (display, playlist) in new_configs.iter().zip(playlists) {
+        match playlist {
+            Some(playlist) => {
+                let i = kept
+                    .iter()
+                    .position(|kept| kept.name == display.name)
+                    .expect("We only skipped opening the displays we kept");
+                let mut kept = kept.swap_remove(i);
+                kept.reload(
+                    playlist,
+                    display.rotation(),
+                    display.schedule(&reloaded.brightness),
+                );
+                displays.push(kept);
+            }
+            None => {
+                log::info!("Opened {}", display.name);
+                displays.push(opened.next().expect("We opened every other display"));
+            }
+        }
+    }
+
+    let moved = reloaded.location != config.location;
+    if let Some(reloaded_ambient) = reloaded_ambient {
+        *ambient = reloaded_ambient;
+    }
+    timers.reload(reloaded_timers);
+    *config = reloaded;
+
+    Ok(moved)
+}
+
+/// Watch our config file and everything it loads
+fn watch(watcher: &mut ConfigWatcher, config: &Config, args: &Args) -> anyhow::Result<()> {
+    let files = config.files();
+    let files: Vec<&Path> = args
+        .config
+        .as_deref()
+        .into_iter()
+        .chain(files.iter().map(PathBuf::as_path))
+        .collect();
+
+    watcher.watch(&files)
+}
+
+/// Our chain over SPI0, spidev or GPIO, unless we're recording or serving a web simulator, optionally
+/// traced
+fn open_max(args: &Args, chained_segments: usize) -> anyhow::Result<Max7219> {
+    let max = transport_config(args).open(chained_segments)?;
+    match &args.trace {
+        Some(path) => max.traced(path),
+        None => Ok(max),
+    }
+}
+
+/// Our transport from our command line
+fn transport_config(args: &Args) -> TransportConfig {
+    if let Some(path) = &args.record {
+        return TransportConfig::Record {
+            path: path.clone(),
+            scale: args.record_scale,
+        };
+    }
+    #[cfg(feature = "max-web")]
+    if let Some(address) = args.web {
+        return TransportConfig::Web { address };
+    }
+    #[cfg(feature = "max-spidev")]
+    if let Some(path) = &args.spidev {
+        return TransportConfig::Spidev {
+            path: path.clone(),
+            speed: args.spi_speed,
+            mode: args.spi_mode,
+        };
+    }
+    #[cfg(feature = "max-gpio")]
+    if let Some(pins) = args.gpio_pins {
+        return TransportConfig::Gpio {
+            chip: args.gpio_chip.clone(),
+            pins,
+        };
+    }
+
+    TransportConfig::Spi { chip_select: 0 }
+}
+
+/// Do whatever `timer` does once it's done
+fn finish(
+    timer: &Timer,
+    displays: &mut [Display],
+    notifications: &mut Notifications,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    log::info!("Our {} timer is done", timer.config.name);
+    match &timer.config.done {
+        TimerAction::Flash => displays.iter_mut().for_each(|display| display.flash(now)),
+        TimerAction::Message { text } => {
+            for display in displays {
+                display.input(&Input::Message(text.clone()), now)?;
+            }
+        }
+        TimerAction::Notify(notification) => {
+            notifications.push(notification.clone(), display_names(displays))
+        }
+        TimerAction::Publish { url } => {
+            let (url, timer) = (url.clone(), timer.clone());
+            tokio::spawn(async move {
+                if let Err(error) = publish(&url, &timer).await {
+                    log::error!("Failed to publish our {} timer: {error}", timer.config.name);
+                }
+            });
+        }
+    }
 
     Ok(())
 }
 
-fn draw_up_arrow<D, E>(x: i32, y: i32, target: &mut D) -> Result<(), E>
-where
-    D: DrawTarget<Color = BinaryColor, Error = E>,
-{
-    Line::new(Point::new(x, y - 3), Point::new(x, y)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x - 2, y - 1), Point::new(x, y - 3)).draw_styled(&LINE_STYLE, target)?;
-    Line::new(Point::new(x + 2, y - 1), Point::new(x, y - 3)).draw_styled(&LINE_STYLE, target)?;
+/// Save our current frame in every format we support
+fn save_snapshots(display: &Display, args: &Args) -> anyhow::Result<()> {
+    let snapshot = display.snapshot()?;
+    log::info!("{} snapshot:\n{}", display.name, snapshot.to_ascii(1));
+
+    let name = format!(
+        "snapshot-{}-{}",
+        display.name,
+        Utc::now().format("%Y%m%dT%H%M%S")
+    );
+    for extension in ["png", "pbm", "txt"] {
+        let path = args.snapshot_dir.join(&name).with_extension(extension);
+        snapshot.save(&path, args.snapshot_scale)?;
+        log::info!("Saved our snapshot to {path:?}");
+    }
 
     Ok(())
 }
@@ -159,6 +648,39 @@ fn spawn_sigint_listener(tx: &Sender<Event>) {
     });
 }
 
+fn spawn_snapshot_listener(tx: &Sender<Event>) -> anyhow::Result<()> {
+    let tx = tx.clone();
+    let mut user_defined1 = signal(SignalKind::user_defined1())?;
+    tokio::spawn(async move {
+        while user_defined1.recv().await.is_some() {
+            send_log(Event::Snapshot, &tx).await;
+        }
+    });
+
+    Ok(())
+}
+
+fn spawn_reload_listener(tx: &Sender<Event>) -> anyhow::Result<()> {
+    let tx = tx.clone();
+    let mut hangup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            send_log(Event::Reload, &tx).await;
+        }
+    });
+
+    Ok(())
+}
+
+fn spawn_exit_timer(tx: &Sender<Event>, after: Duration) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        sleep(after).await;
+        log::info!("Exiting after {after:?}");
+        send_log(Event::Exit, &tx).await;
+    });
+}
+
 fn spawn_display_updater(tx: &Sender<Event>) {
     let tx = tx.clone();
     tokio::spawn(async move {
@@ -170,8 +692,194 @@ fn spawn_display_updater(tx: &Sender<Event>) {
     });
 }
 
+#[cfg(feature = "max-gpio")]
+fn parse_gpio_pins(pins: &str) -> anyhow::Result<[u32; 3]> {
+    let pins = pins
+        .split(',')
+        .map(|pin| pin.trim().parse())
+        .collect::<Result<Vec<u32>, _>>()?;
+
+    pins.try_into()
+        .map_err(|pins| anyhow::anyhow!("Expected 3 GPIO lines (DIN, CLK and CS), not {pins:?}"))
+}
+
 async fn send_log<T>(value: T, tx: &Sender<T>) {
     if let Err(error) = tx.send(value).await {
         log::error!("Error Sending: {error}");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daylight::Location;
+    use crate::testing::at;
+    use std::fs;
+
+    /// Recordings save when their display is dropped, so a display whose file exists was reopened
+    struct Reload {
+        dir: PathBuf,
+        args: Args,
+        config: Config,
+        displays: Vec<Display>,
+        ambient: Option<AmbientBrightness>,
+        timers: Timers,
+        now: DateTime<Utc>,
+    }
+
+    impl Reload {
+        fn new(name: &str, toml: &str) -> Reload {
+            let dir = std::env::temp_dir().join(format!("reload-{name}-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("config.toml");
+            fs::write(&path, toml.replace("{dir}", dir.to_str().unwrap())).unwrap();
+
+            let args = Args::parse_from(["display-board-pi", "--config", path.to_str().unwrap()]);
+            let config = Config::load(args.config.as_deref()).unwrap();
+            let displays = display_configs(&config, &args)
+                .iter()
+                .map(|display| Display::open(display, &config.brightness))
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap();
+            let now = at("2024-06-01T12:00:00Z");
+
+            Reload {
+                dir,
+                args,
+                displays,
+                ambient: config
+                    .ambient
+                    .as_ref()
+                    .map(AmbientBrightness::open)
+                    .transpose()
+                    .unwrap(),
+                config,
+                timers: Timers::new(&[], now).unwrap(),
+                now,
+            }
+        }
+
+        fn reload(&mut self, toml: &str) -> anyhow::Result<bool> {
+            let toml = toml.replace("{dir}", self.dir.to_str().unwrap());
+            fs::write(self.args.config.as_ref().unwrap(), toml).unwrap();
+
+            reload(
+                &self.args,
+                &mut self.config,
+                &mut self.displays,
+                &mut self.ambient,
+                &mut self.timers,
+                self.now,
+            )
+        }
+
+        fn reopened(&self, recording: &str) -> bool {
+            self.dir.join(recording).exists()
+        }
+    }
+
+    impl Drop for Reload {
+        fn drop(&mut self) {
+            self.displays.clear();
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    const CONFIG: &str = r#"
+        [[display]]
+        name = "kitchen"
+        transport = { type = "record", path = "{dir}/kitchen.gif" }
+
+        [[display]]
+        name = "hallway"
+        transport = { type = "record", path = "{dir}/hallway.gif" }
+    "#;
+
+    #[test]
+    fn keeps_everything_when_our_config_is_invalid() {
+        let mut reload = Reload::new("invalid", CONFIG);
+
+        assert!(reload.reload("[[display]]\nname = ").is_err());
+        let missing_layout = CONFIG.replace(
+            "hallway.gif\" }",
+            "hallway.gif\" }\nscenes = [{ type = \"layout\", path = \"{dir}/missing.toml\" }]",
+        );
+        assert!(reload.reload(&missing_layout).is_err());
+
+        assert_eq!(display_names(&reload.displays), ["kitchen", "hallway"]);
+        assert!(matches!(
+            reload.config.displays[1].scenes[..],
+            [SceneConfig::Daylight]
+        ));
+        assert!(!reload.reopened("kitchen.gif") && !reload.reopened("hallway.gif"));
+    }
+
+    #[test]
+    fn only_reopens_chains_that_changed() {
+        let mut reload = Reload::new("changed", CONFIG);
+
+        let changed = format!(
+            "[location]\nlatitude = 51.5\nlongitude = -0.1\n{}",
+            CONFIG
+                .replace("hallway.gif\" }", "hallway.gif\", scale = 4 }")
+                .replace(
+                    "name = \"kitchen\"",
+                    "name = \"kitchen\"\nscenes = [{ type = \"timer\", name = \"tea\" }]"
+                )
+        );
+        assert!(reload.reload(&changed).unwrap());
+
+        assert_eq!(display_names(&reload.displays), ["kitchen", "hallway"]);
+        assert!(!reload.reopened("kitchen.gif"));
+        assert!(reload.reopened("hallway.gif"));
+        assert_eq!(
+            reload.config.location,
+            Location {
+                latitude: 51.5,
+                longitude: -0.1
+            }
+        );
+
+        // staying put isn't a move
+        assert!(!reload.reload(&changed).unwrap());
+    }
+
+    #[test]
+    fn keeps_our_sensor_when_it_didnt_change() {
+        let config =
+            format!("[ambient]\nsensor = {{ type = \"file\", path = \"{{dir}}/lux\" }}\n{CONFIG}");
+        let mut reload = Reload::new("ambient", &config);
+        fs::write(reload.dir.join("lux"), "100").unwrap();
+        let read = reload.ambient.as_mut().unwrap().intensity();
+        assert!(read.is_some());
+
+        // we keep our last reading while our sensor can't be read
+        fs::remove_file(reload.dir.join("lux")).unwrap();
+        reload.reload(&config).unwrap();
+        assert_eq!(reload.ambient.as_mut().unwrap().intensity(), read);
+
+        // a new sensor starts over
+        reload.reload(&config.replace("/lux", "/dark")).unwrap();
+        assert_eq!(reload.ambient.as_mut().unwrap().intensity(), None);
+    }
+
+    #[test]
+    fn keeps_our_old_config_when_a_chain_wont_open() {
+        let mut reload = Reload::new("unopened", CONFIG);
+
+        let untraceable = CONFIG.replace(
+            "hallway.gif\" }",
+            "hallway.gif\" }\ntrace = \"{dir}/missing/trace.log\"",
+        );
+        assert!(reload.reload(&untraceable).is_err());
+
+        assert_eq!(display_names(&reload.displays), ["kitchen", "hallway"]);
+        assert!(!reload.reopened("kitchen.gif"));
+        assert!(reload.config.displays[1].trace.is_none());
+
+        // and our next reload still knows which chains changed
+        fs::create_dir_all(reload.dir.join("missing")).unwrap();
+        reload.reload(&untraceable).unwrap();
+        assert!(reload.dir.join("missing/trace.log").exists());
+    }
+}
@@ -1,7 +1,11 @@
+use anyhow::anyhow;
+use serde::Deserialize;
 use std::mem::size_of;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 #[repr(u8)]
 pub enum Command {
     Noop = 0x0,
@@ -35,6 +39,7 @@ pub const DIGITS: [Command; 8] = [
 ];
 
 pub type Intensity = u8;
+pub const MAX_INTENSITY: Intensity = 0xF;
 pub type ScanLimit = u8;
 
 #[allow(dead_code)]
@@ -52,19 +57,128 @@ pub const DATA_BITS: usize = DATA_BYTES * u8::BITS as usize;
 pub const INSTRUCTION_BYTES: usize = COMMAND_BYTES + DATA_BYTES;
 pub const INSTRUCTION_BITS: usize = INSTRUCTION_BYTES * u8::BITS as usize;
 
-use max_impl::Max7219Impl;
+#[cfg(any(feature = "max-gpio", test))]
+pub mod bitbang;
+pub mod decoder;
+#[cfg(feature = "max-physical")]
+pub mod physical;
+pub mod recorder;
+#[cfg(feature = "max-simulator")]
+pub mod simulator;
+#[cfg(feature = "max-spidev")]
+pub mod spidev;
+pub mod trace;
+#[cfg(feature = "max-web")]
+pub mod web;
+
+/// Where the bytes we'd shift into our chain end up
+pub trait Transport {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()>;
+}
+
+/// How we reach a chain. Every kind parses in every build, but opening one needs its feature (so
+/// their fields aren't always read).
+#[allow(dead_code)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum TransportConfig {
+    /// The Pi's SPI0 on chip select (CE) 0 or 1, or our terminal simulator standing in for it
+    Spi {
+        #[serde(default)]
+        chip_select: u8,
+    },
+    /// A Linux SPI device like `/dev/spidev0.0`
+    Spidev {
+        path: PathBuf,
+        #[serde(default = "default_spi_speed")]
+        speed: u32,
+        #[serde(default)]
+        mode: u8,
+    },
+    /// Bit-banged on GPIO lines: DIN, CLK and CS
+    Gpio {
+        #[serde(default = "default_gpio_chip")]
+        chip: PathBuf,
+        pins: [u32; 3],
+    },
+    /// An animated gif or png
+    Record {
+        path: PathBuf,
+        #[serde(default = "default_record_scale")]
+        scale: usize,
+    },
+    /// A web page served on `address`
+    Web { address: SocketAddr },
+}
+
+impl TransportConfig {
+    pub fn open(&self, chained_segments: usize) -> anyhow::Result<Max7219> {
+        let transport: Box<dyn Transport> = match self {
+            #[cfg(feature = "max-simulator")]
+            TransportConfig::Spi { .. } => Box::new(simulator::Simulator::new(chained_segments)?),
+            #[cfg(all(not(feature = "max-simulator"), feature = "max-physical"))]
+            TransportConfig::Spi { chip_select } => {
+                Box::new(physical::RppalSpi::spi0(*chip_select)?)
+            }
+            #[cfg(feature = "max-spidev")]
+            TransportConfig::Spidev { path, speed, mode } => {
+                Box::new(spidev::LinuxSpi::open(path, *speed, *mode)?)
+            }
+            #[cfg(feature = "max-gpio")]
+            TransportConfig::Gpio { chip, pins } => Box::new(bitbang::cdev::open(chip, pins)?),
+            TransportConfig::Record { path, scale } => {
+                Box::new(recorder::Recorder::new(path, chained_segments, *scale)?)
+            }
+            #[cfg(feature = "max-web")]
+            TransportConfig::Web { address } => {
+                Box::new(web::WebSimulator::new(*address, chained_segments)?)
+            }
+            #[allow(unreachable_patterns)]
+            unsupported => {
+                return Err(anyhow!(
+                    "We were built without support for {unsupported:?}, see our max-* features"
+                ))
+            }
+        };
+
+        Ok(Max7219::new(chained_segments, transport))
+    }
+}
+
+fn default_spi_speed() -> u32 {
+    10_000_000
+}
+
+fn default_gpio_chip() -> PathBuf {
+    PathBuf::from("/dev/gpiochip0")
+}
+
+fn default_record_scale() -> usize {
+    8
+}
 
 pub struct Max7219 {
     chained_segments: usize,
-    max_impl: Max7219Impl,
+    transport: Box<dyn Transport>,
 }
 
 impl Max7219 {
-    pub fn spi0(chained_segments: usize) -> anyhow::Result<Max7219> {
-        Ok(Max7219 {
+    pub fn new(chained_segments: usize, transport: Box<dyn Transport>) -> Max7219 {
+        Max7219 {
             chained_segments,
-            max_impl: Max7219Impl::spi0(chained_segments)?,
-        })
+            transport,
+        }
+    }
+
+    /// Log everything we write to a trace file on its way to our transport
+    pub fn traced(self, path: &Path) -> anyhow::Result<Max7219> {
+        let transport = trace::Tracer::new(path, self.transport)?;
+
+        Ok(Max7219::new(self.chained_segments, Box::new(transport)))
+    }
+
+    pub fn chained_segments(&self) -> usize {
+        self.chained_segments
     }
 
     pub fn set_decode_mode(&mut self, decode_mode: DecodeMode) -> anyhow::Result<()> {
@@ -101,161 +215,6 @@ impl Max7219 {
     pub fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
         log::trace!("Writing: {data:02X?}");
 
-        self.max_impl.write(data)
-    }
-}
-
-#[cfg(feature = "max-physical")]
-mod max_impl {
-    use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
-
-    pub(crate) struct Max7219Impl {
-        spi: Spi,
-    }
-
-    impl Max7219Impl {
-        pub(super) fn spi0(_chained_segments: usize) -> anyhow::Result<Max7219Impl> {
-            let channel = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 10_000_000, Mode::Mode0)?;
-
-            log::info!("Connected to MAX7219");
-            Ok(Max7219Impl { spi: channel })
-        }
-
-        pub fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
-            self.spi.write(data)?;
-
-            Ok(())
-        }
-    }
-}
-
-#[cfg(feature = "max-simulator")]
-mod max_impl {
-    use crate::dot_matrix::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
-    use crate::max7219::Command;
-    use bitvec::order::Msb0;
-    use bitvec::BitArr;
-    use std::io;
-    use std::io::Stdout;
-    use tui::backend::CrosstermBackend;
-    use tui::buffer::Buffer;
-    use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
-    use tui::style::{Color, Style};
-    use tui::widgets::{Block, BorderType, Borders, Widget};
-    use tui::Terminal;
-    use tui_logger::TuiLoggerWidget;
-
-    pub(crate) struct Max7219Impl {
-        terminal: Terminal<CrosstermBackend<Stdout>>,
-        panels: Vec<Panel>,
-    }
-
-    impl Max7219Impl {
-        pub(super) fn spi0(chained_segments: usize) -> anyhow::Result<Max7219Impl> {
-            let stdout = io::stdout();
-            let backend = CrosstermBackend::new(stdout);
-            let mut terminal = Terminal::new(backend)?;
-            terminal.clear()?;
-
-            let mut panels = Vec::with_capacity(chained_segments);
-            panels.resize_with(chained_segments, Panel::default);
-
-            log::info!("Connected to MAX7219 Simulator");
-            Ok(Max7219Impl { terminal, panels })
-        }
-
-        pub fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
-            for i in (0..data.len()).step_by(2) {
-                let command = data[i];
-                let data = data[i + 1];
-                let panel_i = i / 2;
-
-                if let Some(panel) = self.panels.get_mut(panel_i) {
-                    if command == Command::DisplayOn as u8 {
-                        panel.on = data & 0x1 == 0x1;
-                        // log::trace!("Panel {panel_i} on=={}", panel.on);
-                    } else if command >= Command::Digit0 as u8 && command <= Command::Digit7 as u8 {
-                        let row = command - Command::Digit0 as u8;
-                        // log::trace!("Panel {panel_i} Row {row} {data:08b}");
-                        panel.data.data[row as usize] = data;
-                    }
-                }
-            }
-
-            let rendered_panels = self.panels.clone();
-            self.terminal.draw(|frame| {
-                let vertical_layouts = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(vec![
-                        // make sure we have room for our borders
-                        Constraint::Length(2 + DISPLAY_HEIGHT as u16),
-                        Constraint::Min(10),
-                    ])
-                    .split(frame.size());
-
-                let max_block = Block::default()
-                    .borders(Borders::ALL)
-                    .title("MAX7219")
-                    .title_alignment(Alignment::Center)
-                    .border_type(BorderType::Rounded);
-                let border_area = max_block.inner(vertical_layouts[0]);
-                frame.render_widget(max_block, vertical_layouts[0]);
-
-                let panel_layouts = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints(vec![
-                        Constraint::Length(DISPLAY_WIDTH as u16);
-                        rendered_panels.len()
-                    ])
-                    .split(border_area);
-
-                for (i, panel) in rendered_panels.into_iter().enumerate() {
-                    let layout = panel_layouts[i];
-                    frame.render_widget(panel, layout);
-                }
-
-                let logger = TuiLoggerWidget::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Logs")
-                            .title_alignment(Alignment::Center)
-                            .border_type(BorderType::Rounded),
-                    )
-                    .style_error(Style::default().fg(Color::Red))
-                    .style_debug(Style::default().fg(Color::Green))
-                    .style_warn(Style::default().fg(Color::Yellow))
-                    .style_trace(Style::default().fg(Color::Magenta))
-                    .style_info(Style::default().fg(Color::Cyan))
-                    .output_file(false);
-                frame.render_widget(logger, vertical_layouts[1]);
-            })?;
-
-            Ok(())
-        }
-    }
-
-    #[derive(Default, Clone)]
-    struct Panel {
-        on: bool,
-        data: BitArr!(for DISPLAY_WIDTH * DISPLAY_HEIGHT, in u8, Msb0),
-    }
-
-    impl Widget for Panel {
-        fn render(self, area: Rect, buf: &mut Buffer) {
-            if self.on {
-                for y in 0..DISPLAY_HEIGHT.min(area.height as usize) {
-                    for x in 0..DISPLAY_WIDTH.min(area.width as usize) {
-                        let i = (y * DISPLAY_WIDTH) + x;
-                        let screen_x = x as u16 + area.x;
-                        let screen_y = y as u16 + area.y;
-
-                        if self.data[i] {
-                            buf.set_string(screen_x, screen_y, "●", Style::default())
-                        }
-                    }
-                }
-            }
-        }
+        self.transport.write(data)
     }
 }
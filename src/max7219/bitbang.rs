@@ -0,0 +1,163 @@
+#[cfg(feature = "max-gpio")]
+pub mod cdev;
+
+use crate::max7219::Transport;
+
+/// A GPIO line we can drive high or low
+pub trait OutputPin {
+    fn set(&mut self, high: bool) -> anyhow::Result<()>;
+}
+
+/// Shifts our data out on any 3 GPIO lines, for when the hardware SPI bus is busy with other
+/// things. The MAX7219 clocks DIN in on CLK's rising edge (MSB first) and latches the last 16 bits
+/// each chip holds when CS rises.
+pub struct BitBang<P> {
+    din: P,
+    clk: P,
+    cs: P,
+}
+
+impl<P: OutputPin> BitBang<P> {
+    pub fn new(mut din: P, mut clk: P, mut cs: P) -> anyhow::Result<BitBang<P>> {
+        // idle with our clock low and our chain deselected
+        din.set(false)?;
+        clk.set(false)?;
+        cs.set(true)?;
+
+        Ok(BitBang { din, clk, cs })
+    }
+}
+
+impl<P: OutputPin> Transport for BitBang<P> {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        // GPIO through the kernel is far slower than the MAX7219's 100ns minimum clock period,
+        // so we don't need any delays
+        self.cs.set(false)?;
+        for byte in data {
+            for bit in (0..u8::BITS).rev() {
+                self.din.set(byte & (0b1 << bit) != 0)?;
+                self.clk.set(true)?;
+                self.clk.set(false)?;
+            }
+        }
+        self.cs.set(true)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::max7219::{Command, Max7219};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Pin {
+        Din,
+        Clk,
+        Cs,
+    }
+
+    type Waveform = Rc<RefCell<Vec<(Pin, bool)>>>;
+
+    /// Records every edge onto a waveform shared by all of our pins
+    struct MockPin {
+        pin: Pin,
+        waveform: Waveform,
+    }
+
+    impl OutputPin for MockPin {
+        fn set(&mut self, high: bool) -> anyhow::Result<()> {
+            self.waveform.borrow_mut().push((self.pin, high));
+            Ok(())
+        }
+    }
+
+    fn bit_bang() -> (BitBang<MockPin>, Waveform) {
+        let waveform = Waveform::default();
+        let pin = |pin| MockPin {
+            pin,
+            waveform: waveform.clone(),
+        };
+        let bit_bang = BitBang::new(pin(Pin::Din), pin(Pin::Clk), pin(Pin::Cs)).unwrap();
+        waveform.borrow_mut().clear();
+
+        (bit_bang, waveform)
+    }
+
+    /// What a MAX7219 would read: DIN on each rising clock edge between CS falling and rising
+    fn sample(waveform: &[(Pin, bool)]) -> Vec<u8> {
+        assert_eq!(waveform.first(), Some(&(Pin::Cs, false)));
+        assert_eq!(waveform.last(), Some(&(Pin::Cs, true)));
+
+        let (mut din, mut clk) = (false, false);
+        let mut bits = vec![];
+        for (pin, high) in &waveform[1..waveform.len() - 1] {
+            match pin {
+                Pin::Din => {
+                    assert!(!clk, "DIN changed while CLK was high");
+                    din = *high;
+                }
+                Pin::Clk => {
+                    if *high && !clk {
+                        bits.push(din);
+                    }
+                    clk = *high;
+                }
+                Pin::Cs => panic!("CS toggled mid write"),
+            }
+        }
+        assert!(!clk, "CLK should idle low");
+        assert_eq!(bits.len() % 8, 0);
+
+        bits.chunks(8)
+            .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8))
+            .collect()
+    }
+
+    #[test]
+    fn exact_waveform() {
+        let (mut bit_bang, waveform) = bit_bang();
+        bit_bang.write(&[0b10100000]).unwrap();
+
+        let mut expected = vec![(Pin::Cs, false)];
+        for bit in [true, false, true, false, false, false, false, false] {
+            expected.extend([(Pin::Din, bit), (Pin::Clk, true), (Pin::Clk, false)]);
+        }
+        expected.push((Pin::Cs, true));
+        assert_eq!(*waveform.borrow(), expected);
+    }
+
+    #[test]
+    fn every_command() {
+        let commands = [
+            Command::Noop,
+            Command::Digit0,
+            Command::Digit1,
+            Command::Digit2,
+            Command::Digit3,
+            Command::Digit4,
+            Command::Digit5,
+            Command::Digit6,
+            Command::Digit7,
+            Command::DecodeMode,
+            Command::Intensity,
+            Command::ScanLimit,
+            Command::DisplayOn,
+            Command::DisplayTest,
+        ];
+
+        for command in commands {
+            let (bit_bang, waveform) = bit_bang();
+            let mut max = Max7219::new(2, Box::new(bit_bang));
+            max.write_all(command, 0b01011010).unwrap();
+
+            let data = [command as u8, 0b01011010];
+            assert_eq!(sample(&waveform.borrow()), data.repeat(2), "{command:?}");
+            // 1 DIN and 2 CLK edges for each bit, between CS falling and rising
+            assert_eq!(waveform.borrow().len(), 2 + (2 * 16 * 3), "{command:?}");
+        }
+    }
+}
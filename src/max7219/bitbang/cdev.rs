@@ -0,0 +1,40 @@
+use crate::max7219::bitbang::{BitBang, OutputPin};
+
+use anyhow::anyhow;
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use std::path::Path;
+
+const CONSUMER: &str = "display-board-pi";
+
+/// A line from the Linux GPIO character device
+pub struct CdevPin {
+    line: LineHandle,
+}
+
+impl OutputPin for CdevPin {
+    fn set(&mut self, high: bool) -> anyhow::Result<()> {
+        self.line.set_value(high as u8)?;
+
+        Ok(())
+    }
+}
+
+/// Bit-bang our chain on `pins` (DIN, CLK and CS line offsets) of a GPIO chip like
+/// `/dev/gpiochip0`
+pub fn open(chip: &Path, pins: &[u32; 3]) -> anyhow::Result<BitBang<CdevPin>> {
+    let [din, clk, cs] = pins;
+
+    let mut chip = Chip::new(chip).map_err(|e| anyhow!("Failed to open {chip:?}: {e}"))?;
+    let mut pin = |offset: u32, initial: u8| -> anyhow::Result<CdevPin> {
+        let line = chip
+            .get_line(offset)?
+            .request(LineRequestFlags::OUTPUT, initial, CONSUMER)
+            .map_err(|e| anyhow!("Failed to request GPIO line {offset}: {e}"))?;
+        Ok(CdevPin { line })
+    };
+    // chip select idles high, so requesting it doesn't start a transfer
+    let bit_bang = BitBang::new(pin(*din, 0)?, pin(*clk, 0)?, pin(*cs, 1)?)?;
+
+    log::info!("Connected to MAX7219 on GPIO lines DIN={din} CLK={clk} CS={cs}");
+    Ok(bit_bang)
+}
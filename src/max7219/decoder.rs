@@ -0,0 +1,203 @@
+use crate::max7219::{Command, Intensity, ScanLimit, DATA_BITS, DIGITS, MAX_INTENSITY};
+use crate::snapshot::Snapshot;
+
+/// The segments (DP A B C D E F G) the MAX7219 lights for each Code-B character:
+/// 0-9, -, E, H, L, P and blank
+const CODE_B_FONT: [u8; 16] = [
+    0b01111110, 0b00110000, 0b01101101, 0b01111001, 0b00110011, 0b01011011, 0b01011111, 0b01110000,
+    0b01111111, 0b01111011, 0b00000001, 0b01001111, 0b00110111, 0b00001110, 0b01100111, 0b00000000,
+];
+
+/// What one MAX7219 holds, as far as we can tell from the commands we've sent it. Like a real
+/// one, everything starts cleared: shut down, scanning only digit 0 and with no decoding.
+#[derive(Clone, Debug, Default)]
+pub struct Registers {
+    pub on: bool,
+    pub intensity: Intensity,
+    pub scan_limit: ScanLimit,
+    /// Which digits are Code-B decoded, 1 bit per digit
+    pub decode_mode: u8,
+    pub display_test: bool,
+    pub digits: [u8; DIGITS.len()],
+}
+
+impl Registers {
+    /// How bright our lit LEDs are. The MAX7219 PWMs them at (2 * intensity + 1) / 32 and splits
+    /// its time between the digits it scans, so scanning fewer than all 8 makes them brighter
+    /// (relative to a full scan).
+    pub fn duty_cycle(&self) -> f32 {
+        // test mode scans every digit at full intensity
+        let (intensity, scan_limit) = if self.display_test {
+            (MAX_INTENSITY, 7)
+        } else {
+            (self.intensity.min(MAX_INTENSITY), self.scan_limit.min(7))
+        };
+        let pwm = ((2 * intensity as u32 + 1) as f32) / 32.0;
+        let scanned = (scan_limit + 1) as f32;
+
+        (pwm * (DIGITS.len() as f32 / scanned)).min(1.0)
+    }
+
+    /// The segments (or columns) digit `y` lights, after Code-B decoding
+    pub fn segments(&self, y: usize) -> u8 {
+        let data = self.digits[y];
+        if self.decode_mode & (0b1 << y) != 0 {
+            (data & 0b10000000) | CODE_B_FONT[(data & 0xF) as usize]
+        } else {
+            data
+        }
+    }
+
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        // test mode lights everything, ignoring (but keeping) our other registers
+        if self.display_test {
+            return true;
+        }
+
+        self.on && y <= self.scan_limit as usize && self.segments(y) & (0b10000000 >> x) != 0
+    }
+}
+
+/// Follows the commands we send down a chain of MAX7219s, so we can show what real ones would
+pub struct ChainDecoder {
+    pub panels: Vec<Registers>,
+}
+
+impl ChainDecoder {
+    pub fn new(chained_segments: usize) -> ChainDecoder {
+        ChainDecoder {
+            panels: vec![Registers::default(); chained_segments],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.panels.len() * DATA_BITS
+    }
+
+    pub fn height(&self) -> usize {
+        DIGITS.len()
+    }
+
+    /// Apply one write to our chain, the first instruction lands on our first (left most) panel.
+    /// Returns true if this write finished a frame, either by writing our last digit row or by
+    /// changing a register.
+    pub fn write(&mut self, data: &[u8]) -> bool {
+        let mut frame_finished = false;
+        for (panel, instruction) in self.panels.iter_mut().zip(data.chunks_exact(2)) {
+            let (command, data) = (instruction[0], instruction[1]);
+
+            if command >= Command::Digit0 as u8 && command <= Command::Digit7 as u8 {
+                panel.digits[(command - Command::Digit0 as u8) as usize] = data;
+                frame_finished |= command == Command::Digit7 as u8;
+            } else if command == Command::DisplayOn as u8 {
+                panel.on = data & 0x1 == 0x1;
+                frame_finished = true;
+            } else if command == Command::Intensity as u8 {
+                panel.intensity = data & 0xF;
+                frame_finished = true;
+            } else if command == Command::ScanLimit as u8 {
+                panel.scan_limit = data & 0x7;
+                frame_finished = true;
+            } else if command == Command::DecodeMode as u8 {
+                panel.decode_mode = data;
+                frame_finished = true;
+            } else if command == Command::DisplayTest as u8 {
+                panel.display_test = data & 0x1 == 0x1;
+                frame_finished = true;
+            }
+        }
+
+        frame_finished
+    }
+
+    /// How bright each LED is, from 0 (off) to 255
+    pub fn brightness(&self, x: usize, y: usize) -> u8 {
+        let panel = &self.panels[x / DATA_BITS];
+        if panel.is_lit(x % DATA_BITS, y) {
+            (panel.duty_cycle() * u8::MAX as f32).round() as u8
+        } else {
+            0
+        }
+    }
+
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        self.panels[x / DATA_BITS].is_lit(x % DATA_BITS, y)
+    }
+
+    /// Which LEDs are lit, ignoring their intensity
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                snapshot.set(x, y, if self.is_lit(x, y) { u8::MAX } else { 0 });
+            }
+        }
+
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoder(instructions: &[(Command, u8)]) -> ChainDecoder {
+        let mut decoder = ChainDecoder::new(1);
+        for (command, data) in instructions {
+            decoder.write(&[*command as u8, *data]);
+        }
+
+        decoder
+    }
+
+    fn row(decoder: &ChainDecoder, y: usize) -> String {
+        (0..decoder.width())
+            .map(|x| if decoder.is_lit(x, y) { '#' } else { '.' })
+            .collect()
+    }
+
+    #[test]
+    fn starts_shut_down() {
+        let decoder = decoder(&[(Command::Digit0, 0xFF)]);
+        assert_eq!(row(&decoder, 0), "........");
+    }
+
+    #[test]
+    fn scan_limit_hides_digits() {
+        let decoder = decoder(&[
+            (Command::DisplayOn, 1),
+            (Command::ScanLimit, 1),
+            (Command::Digit1, 0b10000001),
+            (Command::Digit2, 0xFF),
+        ]);
+        assert_eq!(row(&decoder, 1), "#......#");
+        assert_eq!(row(&decoder, 2), "........");
+        // fewer digits share our scan time, so they're brighter
+        assert_eq!(decoder.panels[0].duty_cycle(), 4.0 / 32.0);
+    }
+
+    #[test]
+    fn code_b_decodes_digits() {
+        let decoder = decoder(&[
+            (Command::DisplayOn, 1),
+            (Command::ScanLimit, 7),
+            (Command::DecodeMode, 0b00000001),
+            // 3 with its decimal point
+            (Command::Digit0, 0b10000011),
+            (Command::Digit1, 0b00000011),
+        ]);
+        assert_eq!(row(&decoder, 0), "#####..#");
+        assert_eq!(row(&decoder, 1), "......##");
+    }
+
+    #[test]
+    fn display_test_overrides_everything() {
+        let mut decoder = decoder(&[(Command::Intensity, 0), (Command::DisplayTest, 1)]);
+        assert_eq!(row(&decoder, 7), "########");
+        assert_eq!(decoder.brightness(0, 7), 247);
+
+        decoder.write(&[Command::DisplayTest as u8, 0]);
+        assert_eq!(row(&decoder, 7), "........");
+    }
+}
@@ -0,0 +1,38 @@
+use crate::max7219::Transport;
+
+use anyhow::anyhow;
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+
+/// Our simulator takes over the `spi` transport when it's built in
+#[cfg_attr(feature = "max-simulator", allow(dead_code))]
+pub struct RppalSpi {
+    spi: Spi,
+}
+
+#[cfg_attr(feature = "max-simulator", allow(dead_code))]
+impl RppalSpi {
+    /// SPI0 on chip select (CE) 0 or 1
+    pub fn spi0(chip_select: u8) -> anyhow::Result<RppalSpi> {
+        let slave_select = match chip_select {
+            0 => SlaveSelect::Ss0,
+            1 => SlaveSelect::Ss1,
+            _ => {
+                return Err(anyhow!(
+                    "SPI0 only has chip selects 0 and 1, not {chip_select}"
+                ))
+            }
+        };
+        let channel = Spi::new(Bus::Spi0, slave_select, 10_000_000, Mode::Mode0)?;
+
+        log::info!("Connected to MAX7219 on SPI0 CE{chip_select}");
+        Ok(RppalSpi { spi: channel })
+    }
+}
+
+impl Transport for RppalSpi {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        self.spi.write(data)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use crate::max7219::decoder::ChainDecoder;
+use crate::max7219::Transport;
+
+use anyhow::anyhow;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// GIF delays are in hundredths of a second and most viewers won't go faster than 20ms, so
+/// anything quicker (dithering) is averaged together
+const MIN_FRAME_DURATION: Duration = Duration::from_millis(20);
+
+const BACKGROUND: [u8; 3] = [0x10, 0x10, 0x10];
+const LED_OFF: [u8; 3] = [0x38, 0x04, 0x04];
+const LED_ON: [u8; 3] = [0xFF, 0x30, 0x20];
+/// How many shades of red our LEDs have, on top of our background color
+const LED_SHADES: usize = 64;
+
+/// A headless simulator that decodes everything we send our chain into frames, and saves them
+/// as an animated GIF (`.gif`) or APNG (`.png`) when we're dropped
+pub struct Recorder {
+    path: PathBuf,
+    /// How many pixels wide each LED is
+    scale: usize,
+    decoder: ChainDecoder,
+    frames: Vec<RecordedFrame>,
+    /// What our LEDs are showing right now and since when
+    current: Vec<u8>,
+    current_since: Instant,
+    /// How long each LED has been lit (weighted by brightness) since our window started
+    window: Vec<f64>,
+    window_start: Instant,
+}
+
+struct RecordedFrame {
+    duration: Duration,
+    /// The brightness of each LED from 0 (off) to 255
+    leds: Vec<u8>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path, chained_segments: usize, scale: usize) -> anyhow::Result<Recorder> {
+        Format::of(path)?;
+
+        let decoder = ChainDecoder::new(chained_segments);
+        let leds = decoder.width() * decoder.height();
+        let now = Instant::now();
+
+        log::info!("Recording MAX7219 frames to {path:?}");
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            scale: scale.max(1),
+            decoder,
+            frames: vec![],
+            current: vec![0; leds],
+            current_since: now,
+            window: vec![0.0; leds],
+            window_start: now,
+        })
+    }
+
+    /// Average everything our LEDs showed since our window started into a frame
+    fn accumulate(&mut self, now: Instant) {
+        let shown = (now - self.current_since).as_secs_f64();
+        for (total, brightness) in self.window.iter_mut().zip(self.current.iter()) {
+            *total += *brightness as f64 * shown;
+        }
+        self.current_since = now;
+
+        if now - self.window_start >= MIN_FRAME_DURATION {
+            self.finish_window(now);
+        }
+    }
+
+    fn finish_window(&mut self, now: Instant) {
+        let duration = now - self.window_start;
+        let leds = self
+            .window
+            .iter()
+            .map(|total| (total / duration.as_secs_f64()).round() as u8)
+            .collect();
+        self.push_frame(duration, leds);
+
+        self.window.fill(0.0);
+        self.window_start = now;
+    }
+
+    fn push_frame(&mut self, duration: Duration, leds: Vec<u8>) {
+        match self.frames.last_mut() {
+            Some(last) if last.leds == leds => last.duration += duration,
+            _ => self.frames.push(RecordedFrame { duration, leds }),
+        }
+    }
+
+    fn current_leds(&self) -> Vec<u8> {
+        let mut leds = Vec::with_capacity(self.current.len());
+        for y in 0..self.decoder.height() {
+            for x in 0..self.decoder.width() {
+                leds.push(self.decoder.brightness(x, y));
+            }
+        }
+
+        leds
+    }
+
+    fn save(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        self.accumulate(now);
+        // keep whatever didn't fill up a full frame
+        if now > self.window_start {
+            self.finish_window(now);
+        }
+
+        if self.frames.is_empty() {
+            return Err(anyhow!("We didn't record any frames"));
+        }
+
+        let file = BufWriter::new(File::create(&self.path)?);
+        match Format::of(&self.path)? {
+            Format::Gif => self.save_gif(file)?,
+            Format::Apng => self.save_apng(file)?,
+        }
+
+        log::info!("Saved {} frames to {:?}", self.frames.len(), self.path);
+        Ok(())
+    }
+
+    fn save_gif(&self, file: BufWriter<File>) -> anyhow::Result<()> {
+        let (width, height) = self.image_size();
+        let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &palette())?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        for frame in &self.frames {
+            let gif_frame = gif::Frame {
+                width: width as u16,
+                height: height as u16,
+                // hundredths of a second
+                delay: (frame.duration.as_millis() / 10).clamp(2, u16::MAX as u128) as u16,
+                buffer: Cow::Owned(self.render(frame)),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn save_apng(&self, file: BufWriter<File>) -> anyhow::Result<()> {
+        let (width, height) = self.image_size();
+        let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette());
+        // 0 plays is forever
+        encoder.set_animated(self.frames.len() as u32, 0)?;
+
+        let mut writer = encoder.write_header()?;
+        for frame in &self.frames {
+            let centiseconds = (frame.duration.as_millis() / 10).clamp(1, u16::MAX as u128);
+            writer.set_frame_delay(centiseconds as u16, 100)?;
+            writer.write_image_data(&self.render(frame))?;
+        }
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    fn image_size(&self) -> (usize, usize) {
+        (
+            self.decoder.width() * self.scale,
+            self.decoder.height() * self.scale,
+        )
+    }
+
+    /// Draw each LED as a round dot on our background, as palette indexes
+    fn render(&self, frame: &RecordedFrame) -> Vec<u8> {
+        let (width, height) = self.image_size();
+        let center = self.scale as f32 / 2.0;
+        let radius = self.scale as f32 * 0.42;
+
+        let mut image = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let dx = (x % self.scale) as f32 + 0.5 - center;
+                let dy = (y % self.scale) as f32 + 0.5 - center;
+                // tiny LEDs don't have room to be round
+                if self.scale < 3 || (dx * dx) + (dy * dy) <= radius * radius {
+                    let led =
+                        frame.leds[((y / self.scale) * self.decoder.width()) + (x / self.scale)];
+                    image.push(1 + shade(led) as u8);
+                } else {
+                    image.push(0);
+                }
+            }
+        }
+
+        image
+    }
+}
+
+impl Transport for Recorder {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if self.decoder.write(data) {
+            self.accumulate(Instant::now());
+            self.current = self.current_leds();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.save() {
+            log::error!("Failed to save our recording to {:?}: {error:?}", self.path)
+        }
+    }
+}
+
+enum Format {
+    Gif,
+    Apng,
+}
+
+impl Format {
+    fn of(path: &Path) -> anyhow::Result<Format> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gif") => Ok(Format::Gif),
+            Some("png") | Some("apng") => Ok(Format::Apng),
+            _ => Err(anyhow!(
+                "Unknown recording format for {path:?}, expected gif or png"
+            )),
+        }
+    }
+}
+
+/// Which of our LED shades to use, brightness is perceived on a curve so dim LEDs stay visible
+fn shade(brightness: u8) -> usize {
+    let perceived = (brightness as f32 / u8::MAX as f32).powf(1.0 / 2.2);
+    ((perceived * (LED_SHADES - 1) as f32).round() as usize).min(LED_SHADES - 1)
+}
+
+/// Our background followed by our LED shades from off to fully on, as flat RGB
+fn palette() -> Vec<u8> {
+    let mut palette = BACKGROUND.to_vec();
+    for shade in 0..LED_SHADES {
+        let t = shade as f32 / (LED_SHADES - 1) as f32;
+        for (off, on) in LED_OFF.iter().zip(LED_ON.iter()) {
+            palette.push((*off as f32 + ((*on as f32 - *off as f32) * t)).round() as u8);
+        }
+    }
+
+    palette
+}
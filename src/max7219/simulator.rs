@@ -0,0 +1,258 @@
+use crate::max7219::decoder::ChainDecoder;
+use crate::max7219::Transport;
+use anyhow::anyhow;
+use ratatui::backend::CrosstermBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, BorderType, Borders, Widget};
+use ratatui::Terminal;
+use std::io;
+use std::io::Stdout;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tui_logger::TuiLoggerWidget;
+
+/// Redrawing our terminal is slow, so when we're sent frames faster than this (dithering) we
+/// average them together instead
+const MIN_RENDER_INTERVAL: Duration = Duration::from_millis(40);
+
+/// We draw over our whole terminal, so there's only room for one of us
+static OPENED: AtomicBool = AtomicBool::new(false);
+
+/// Gray levels for a pixel lit part of the time
+const SHADES: [&str; 3] = ["░", "▒", "▓"];
+
+/// The colors of our dimmest and brightest lit LEDs
+const LED_DIM: [u8; 3] = [0x50, 0x08, 0x08];
+const LED_BRIGHT: [u8; 3] = [0xFF, 0x40, 0x30];
+
+/// Shows what a chain of MAX7219s would, modelling all of their registers through our
+/// `ChainDecoder`
+pub struct Simulator {
+    decoder: ChainDecoder,
+    screen: Arc<Mutex<Screen>>,
+}
+
+/// Our terminal and what we'll draw on it next
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    width: usize,
+    height: usize,
+    /// What our LEDs showed since our last render
+    leds: Vec<Led>,
+    frames: u16,
+    last_render: Option<Instant>,
+    /// We were sent a frame too soon after our last render, so it's waiting on our next
+    pending: bool,
+}
+
+impl Simulator {
+    pub fn new(chained_segments: usize) -> anyhow::Result<Simulator> {
+        if OPENED.swap(true, Ordering::SeqCst) {
+            return Err(anyhow!(
+                "Only 1 display can use our terminal simulator, record or serve the others"
+            ));
+        }
+
+        let terminal = Simulator::open_terminal().inspect_err(|_| {
+            // nothing's drawing over our terminal, so someone can try again
+            OPENED.store(false, Ordering::SeqCst);
+        })?;
+
+        let decoder = ChainDecoder::new(chained_segments);
+        let screen = Arc::new(Mutex::new(Screen {
+            terminal,
+            width: decoder.width(),
+            height: decoder.height(),
+            leds: vec![Led::default(); decoder.width() * decoder.height()],
+            frames: 0,
+            last_render: None,
+            pending: false,
+        }));
+
+        // draw whatever we held back once we're allowed, even if nothing else is sent after it
+        let pending = Arc::downgrade(&screen);
+        thread::spawn(move || loop {
+            thread::sleep(MIN_RENDER_INTERVAL);
+            let Some(screen) = pending.upgrade() else {
+                return;
+            };
+            let mut screen = screen.lock().expect("Our screen isn't poisoned");
+            if screen.pending && !screen.throttled(Instant::now()) {
+                if let Err(error) = screen.render() {
+                    log::error!("Error drawing our simulator: {error}");
+                }
+            }
+        });
+
+        log::info!("Connected to MAX7219 Simulator");
+        Ok(Simulator { decoder, screen })
+    }
+
+    fn open_terminal() -> anyhow::Result<Terminal<CrosstermBackend<Stdout>>> {
+        let stdout = io::stdout();
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.clear()?;
+
+        Ok(terminal)
+    }
+}
+
+impl Screen {
+    /// Add what our chain is showing right now to what we'll show on our next render
+    fn accumulate(&mut self, decoder: &ChainDecoder) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let brightness = decoder.brightness(x, y);
+                if brightness > 0 {
+                    let led = &mut self.leds[(y * self.width) + x];
+                    led.lit = led.lit.saturating_add(1);
+                    led.brightness += brightness as u32;
+                }
+            }
+        }
+        self.frames = self.frames.saturating_add(1);
+    }
+
+    fn throttled(&self, now: Instant) -> bool {
+        self.last_render
+            .is_some_and(|last_render| now - last_render < MIN_RENDER_INTERVAL)
+    }
+
+    fn render(&mut self) -> anyhow::Result<()> {
+        self.last_render = Some(Instant::now());
+        self.pending = false;
+
+        let leds = Leds {
+            width: self.width,
+            frames: self.frames,
+            leds: self.leds.clone(),
+        };
+        self.leds.fill(Led::default());
+        self.frames = 0;
+
+        let height = self.height;
+        self.terminal.draw(|frame| {
+            let vertical_layouts = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![
+                    // make sure we have room for our borders
+                    Constraint::Length(2 + height as u16),
+                    Constraint::Min(10),
+                ])
+                .split(frame.area());
+
+            let max_block = Block::default()
+                .borders(Borders::ALL)
+                .title("MAX7219")
+                .title_alignment(Alignment::Center)
+                .border_type(BorderType::Rounded);
+            let border_area = max_block.inner(vertical_layouts[0]);
+            frame.render_widget(max_block, vertical_layouts[0]);
+
+            frame.render_widget(leds, border_area);
+
+            let logger = TuiLoggerWidget::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Logs")
+                        .title_alignment(Alignment::Center)
+                        .border_type(BorderType::Rounded),
+                )
+                .style_error(Style::default().fg(Color::Red))
+                .style_debug(Style::default().fg(Color::Green))
+                .style_warn(Style::default().fg(Color::Yellow))
+                .style_trace(Style::default().fg(Color::Magenta))
+                .style_info(Style::default().fg(Color::Cyan))
+                .output_file(false);
+            frame.render_widget(logger, vertical_layouts[1]);
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Transport for Simulator {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if !self.decoder.write(data) {
+            return Ok(());
+        }
+
+        let mut screen = self.screen.lock().expect("Our screen isn't poisoned");
+        screen.accumulate(&self.decoder);
+        if screen.throttled(Instant::now()) {
+            screen.pending = true;
+            return Ok(());
+        }
+
+        screen.render()
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        // our terminal's free for whoever opens a simulator next (a reload, say)
+        OPENED.store(false, Ordering::SeqCst);
+    }
+}
+
+/// How one LED looked across the frames since our last render
+#[derive(Default, Clone, Copy)]
+struct Led {
+    /// How many frames we were lit for
+    lit: u16,
+    /// Our total brightness across those frames, each from 0 to 255
+    brightness: u32,
+}
+
+struct Leds {
+    width: usize,
+    frames: u16,
+    leds: Vec<Led>,
+}
+
+impl Widget for Leds {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = self.leds.len() / self.width.max(1);
+        for y in 0..height.min(area.height as usize) {
+            for x in 0..self.width.min(area.width as usize) {
+                let led = self.leds[(y * self.width) + x];
+                if led.lit == 0 {
+                    continue;
+                }
+
+                // dithering shows up as how often we're lit, our intensity as our color
+                let lit = led.lit as f32 / self.frames.max(1) as f32;
+                let symbol = if lit >= 1.0 {
+                    "●"
+                } else {
+                    SHADES[((lit * SHADES.len() as f32) as usize).min(SHADES.len() - 1)]
+                };
+                let brightness = led.brightness as f32 / (led.lit as f32 * u8::MAX as f32);
+
+                buf.set_string(
+                    x as u16 + area.x,
+                    y as u16 + area.y,
+                    symbol,
+                    Style::default().fg(color(brightness)),
+                );
+            }
+        }
+    }
+}
+
+/// Our LED color at a brightness from 0 to 1, brightness is perceived on a curve so dim LEDs
+/// stay visible
+fn color(brightness: f32) -> Color {
+    let perceived = brightness.clamp(0.0, 1.0).powf(1.0 / 2.2);
+    let [r, g, b] = [0, 1, 2].map(|i| {
+        (LED_DIM[i] as f32 + ((LED_BRIGHT[i] as f32 - LED_DIM[i] as f32) * perceived)).round() as u8
+    });
+
+    Color::Rgb(r, g, b)
+}
@@ -0,0 +1,45 @@
+use crate::max7219::Transport;
+
+use anyhow::anyhow;
+use spidev::{SpiModeFlags, Spidev, SpidevOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Any Linux SPI device (`/dev/spidevX.Y`), so we aren't tied to a Raspberry Pi
+pub struct LinuxSpi {
+    spi: Spidev,
+}
+
+impl LinuxSpi {
+    pub fn open(path: &Path, speed_hz: u32, mode: u8) -> anyhow::Result<LinuxSpi> {
+        let mode = match mode {
+            0 => SpiModeFlags::SPI_MODE_0,
+            1 => SpiModeFlags::SPI_MODE_1,
+            2 => SpiModeFlags::SPI_MODE_2,
+            3 => SpiModeFlags::SPI_MODE_3,
+            _ => return Err(anyhow!("Unknown SPI mode {mode}, expected 0-3")),
+        };
+
+        let mut spi =
+            Spidev::open(path).map_err(|e| anyhow!("Failed to open SPI device {path:?}: {e}"))?;
+        spi.configure(
+            &SpidevOptions::new()
+                .bits_per_word(8)
+                .max_speed_hz(speed_hz)
+                .mode(mode)
+                .build(),
+        )?;
+
+        log::info!("Connected to MAX7219 on {path:?} at {speed_hz}Hz");
+        Ok(LinuxSpi { spi })
+    }
+}
+
+impl Transport for LinuxSpi {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        // our chain only latches once chip select goes high, so it all needs to be 1 transfer
+        self.spi.write_all(data)?;
+
+        Ok(())
+    }
+}
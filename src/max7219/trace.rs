@@ -0,0 +1,190 @@
+use crate::max7219::{Command, Max7219, Transport};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::time::sleep_until;
+
+/// How often we save our frames, so a crash loses at most this much without a flush for every
+/// one of the ~1000 frames a second we dither
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One line of our JSONL trace
+#[derive(Serialize, Deserialize, Debug)]
+struct TraceEntry {
+    /// Microseconds since our trace started
+    us: u64,
+    /// Everything shifted into our chain, as hex
+    data: String,
+}
+
+/// Logs every write with a timestamp before passing it on, so we can replay what a board sent
+pub struct Tracer {
+    transport: Box<dyn Transport>,
+    file: BufWriter<File>,
+    start: Instant,
+    flushed: Instant,
+}
+
+impl Tracer {
+    pub fn new(path: &Path, transport: Box<dyn Transport>) -> anyhow::Result<Tracer> {
+        log::info!("Tracing MAX7219 writes to {path:?}");
+        Ok(Tracer {
+            transport,
+            file: BufWriter::new(File::create(path)?),
+            start: Instant::now(),
+            flushed: Instant::now(),
+        })
+    }
+}
+
+impl Transport for Tracer {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let entry = TraceEntry {
+            us: self.start.elapsed().as_micros() as u64,
+            data: data.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            }),
+        };
+        serde_json::to_writer(&mut self.file, &entry)?;
+        self.file.write_all(b"\n")?;
+        // we're usually stopped by a crash, kill or power cut, so save our frames every so often
+        // and anything that isn't a frame (like a brightness change) right away
+        let frame = data.first().is_some_and(|&command| {
+            (Command::Digit0 as u8..=Command::Digit7 as u8).contains(&command)
+        });
+        if !frame || self.flushed.elapsed() >= FLUSH_INTERVAL {
+            self.file.flush()?;
+            self.flushed = Instant::now();
+        }
+
+        self.transport.write(data)
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if let Err(error) = self.file.flush() {
+            log::error!("Failed to save the end of our trace: {error}");
+        }
+    }
+}
+
+/// A trace we can feed back into any chain
+pub struct Trace {
+    writes: Vec<(Duration, Vec<u8>)>,
+}
+
+impl Trace {
+    pub fn load(path: &Path) -> anyhow::Result<Trace> {
+        let mut writes = vec![];
+        for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: TraceEntry = serde_json::from_str(&line)
+                .map_err(|e| anyhow!("Bad trace entry on line {}: {e}", i + 1))?;
+            writes.push((Duration::from_micros(entry.us), decode_hex(&entry.data)?));
+        }
+
+        if writes.is_empty() {
+            return Err(anyhow!("{path:?} doesn't have any writes"));
+        }
+        Ok(Trace { writes })
+    }
+
+    /// How long our chain was, from the size of our first write
+    pub fn chained_segments(&self) -> usize {
+        self.writes[0].1.len() / 2
+    }
+
+    /// Send our writes to `max` with their original timing
+    pub async fn replay(&self, max: &mut Max7219) -> anyhow::Result<()> {
+        log::info!("Replaying {} writes", self.writes.len());
+        let start = tokio::time::Instant::now();
+        for (at, data) in &self.writes {
+            sleep_until(start + *at).await;
+            max.write(data)?;
+        }
+        log::info!("Finished replaying");
+
+        Ok(())
+    }
+}
+
+fn decode_hex(hex: &str) -> anyhow::Result<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        return Err(anyhow!("Bad hex {hex}"));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| anyhow!("Bad hex {hex}: {e}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RecordingTransport;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn decodes_hex() {
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode_hex("0aFF10").unwrap(), [0x0a, 0xff, 0x10]);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert!(decode_hex("é1").is_err());
+    }
+
+    #[tokio::test]
+    async fn replays_what_we_traced() {
+        let path = env::temp_dir().join(format!("trace-{}.jsonl", std::process::id()));
+        let traced = RecordingTransport::new(2);
+        let mut max = Max7219::new(2, Box::new(traced.clone()))
+            .traced(&path)
+            .unwrap();
+        let frame = |max: &mut Max7219, lit: u8| {
+            for digit in 1..=8 {
+                max.write(&[digit, lit, digit, lit]).unwrap();
+            }
+        };
+
+        max.write(&[Command::Intensity as u8, 0x3, Command::Intensity as u8, 0x3])
+            .unwrap();
+        // anything that isn't a frame is saved right away, and the rest once we're dropped
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, 1);
+        frame(&mut max, 0xff);
+        std::thread::sleep(Duration::from_millis(50));
+        frame(&mut max, 0x0f);
+        // an empty write is traced like any other
+        max.write(&[]).unwrap();
+        drop(max);
+
+        let trace = Trace::load(&path).unwrap();
+        assert_eq!(trace.chained_segments(), 2);
+        assert_eq!(trace.writes.len(), 18);
+        assert_eq!(trace.writes[16].1, [8, 0x0f, 8, 0x0f]);
+        let gap = trace.writes[9].0 - trace.writes[8].0;
+        assert!(gap >= Duration::from_millis(50), "{gap:?}");
+
+        let transport = RecordingTransport::new(2);
+        let mut replayed = Max7219::new(2, Box::new(transport.clone()));
+        let start = Instant::now();
+        trace.replay(&mut replayed).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(transport.last_frame(), traced.last_frame());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
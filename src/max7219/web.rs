@@ -0,0 +1,175 @@
+use crate::max7219::decoder::ChainDecoder;
+use crate::max7219::Transport;
+
+use axum::extract::State;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::WatchStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Browsers don't need more than 25 frames a second, anything quicker (dithering) is averaged
+/// together
+const MIN_SEND_INTERVAL: Duration = Duration::from_millis(40);
+
+const INDEX: &str = include_str!("web/index.html");
+
+/// Serves a page drawing our chain as LEDs, streaming it frames decoded from what we're sent, so
+/// anyone on our network can watch without a terminal or real hardware
+pub struct WebSimulator {
+    decoder: ChainDecoder,
+    next: Arc<Mutex<NextFrame>>,
+    server: JoinHandle<()>,
+    /// Sends the frame we held back once we're allowed to
+    sends_pending: JoinHandle<()>,
+}
+
+/// What we'll send our browsers next
+struct NextFrame {
+    /// Our total brightness for each LED since our last frame
+    totals: Vec<u32>,
+    frames: u32,
+    last_send: Option<Instant>,
+    /// We were sent a frame too soon after our last one, so it's waiting to go out
+    pending: bool,
+    sender: watch::Sender<Frame>,
+}
+
+/// What we send our browsers
+#[derive(Clone, Debug, Serialize)]
+struct Frame {
+    panels: usize,
+    width: usize,
+    height: usize,
+    /// The brightness of each LED from 0 (off) to 255, row by row
+    leds: Vec<u8>,
+}
+
+impl WebSimulator {
+    pub fn new(address: SocketAddr, chained_segments: usize) -> anyhow::Result<WebSimulator> {
+        let decoder = ChainDecoder::new(chained_segments);
+        let leds = decoder.width() * decoder.height();
+        let (sender, receiver) = watch::channel(Frame {
+            panels: chained_segments,
+            width: decoder.width(),
+            height: decoder.height(),
+            leds: vec![0; leds],
+        });
+
+        // bind right away so a bad address fails our startup
+        let listener = std::net::TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        let app = Router::new()
+            .route("/", get(|| async { Html(INDEX) }))
+            .route("/frames", get(frames))
+            .with_state(receiver);
+        let server = tokio::spawn(async move {
+            if let Err(error) = axum::serve(listener, app).await {
+                log::error!("Our web simulator stopped: {error}");
+            }
+        });
+
+        let next = Arc::new(Mutex::new(NextFrame {
+            totals: vec![0; leds],
+            frames: 0,
+            last_send: None,
+            pending: false,
+            sender,
+        }));
+        let pending = next.clone();
+        let sends_pending = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MIN_SEND_INTERVAL);
+            loop {
+                interval.tick().await;
+                let mut next = pending.lock().expect("Our next frame isn't poisoned");
+                if next.pending && !next.throttled(Instant::now()) {
+                    next.send();
+                }
+            }
+        });
+
+        log::info!("Serving our MAX7219 web simulator on http://{address}");
+        Ok(WebSimulator {
+            decoder,
+            next,
+            server,
+            sends_pending,
+        })
+    }
+}
+
+impl NextFrame {
+    /// Add what our chain is showing right now to our next frame
+    fn accumulate(&mut self, decoder: &ChainDecoder) {
+        for y in 0..decoder.height() {
+            for x in 0..decoder.width() {
+                self.totals[(y * decoder.width()) + x] += decoder.brightness(x, y) as u32;
+            }
+        }
+        self.frames += 1;
+    }
+
+    fn throttled(&self, now: Instant) -> bool {
+        self.last_send
+            .is_some_and(|last_send| now - last_send < MIN_SEND_INTERVAL)
+    }
+
+    fn send(&mut self) {
+        self.last_send = Some(Instant::now());
+        self.pending = false;
+
+        let leds = self
+            .totals
+            .iter()
+            .map(|total| (total / self.frames.max(1)) as u8)
+            .collect();
+        self.totals.fill(0);
+        self.frames = 0;
+
+        // nobody watching is fine, they'll get our latest frame when they connect
+        self.sender.send_modify(|frame| frame.leds = leds);
+    }
+}
+
+impl Transport for WebSimulator {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        if !self.decoder.write(data) {
+            return Ok(());
+        }
+
+        let mut next = self.next.lock().expect("Our next frame isn't poisoned");
+        next.accumulate(&self.decoder);
+        if next.throttled(Instant::now()) {
+            next.pending = true;
+        } else {
+            next.send();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for WebSimulator {
+    fn drop(&mut self) {
+        // free our address for whoever serves it next (a reload, say)
+        self.server.abort();
+        self.sends_pending.abort();
+    }
+}
+
+/// Our latest frame, then every new one as server-sent events
+async fn frames(
+    State(receiver): State<watch::Receiver<Frame>>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let stream = WatchStream::new(receiver).map(|frame| Event::default().json_data(frame));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
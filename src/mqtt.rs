@@ -0,0 +1,142 @@
+//! Queue notifications from an MQTT topic. Messages are a JSON notification (like our remote
+//! control takes), or just some text to scroll.
+
+use crate::Event;
+
+use serde::Deserialize;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+/// The `[mqtt]` section of our config. It parses in every build, but subscribing needs our `mqtt`
+/// feature.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+pub struct MqttConfig {
+    /// Our broker, like `homeassistant.local`
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default = "default_topic")]
+    pub topic: String,
+}
+
+fn default_port() -> u16 {
+    1883
+}
+
+fn default_client_id() -> String {
+    "display-board-pi".to_string()
+}
+
+fn default_topic() -> String {
+    "display-board/notifications".to_string()
+}
+
+/// Listens to our topic for as long as we're around
+pub struct Mqtt {
+    subscriber: JoinHandle<()>,
+}
+
+impl Mqtt {
+    #[cfg(feature = "mqtt")]
+    pub fn spawn(config: &MqttConfig, tx: &Sender<Event>) -> anyhow::Result<Mqtt> {
+        use rumqttc::{AsyncClient, MqttOptions, Packet, QoS};
+        use std::time::Duration;
+        use tokio::time::sleep;
+
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+        let (client, mut event_loop) = AsyncClient::new(options, 8);
+
+        let (config, tx) = (config.clone(), tx.clone());
+        let subscriber = tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    // our broker forgets our subscription when we reconnect
+                    Ok(rumqttc::Event::Incoming(Packet::ConnAck(_))) => {
+                        log::info!("Listening for notifications on {}", config.topic);
+                        if let Err(error) = client.subscribe(&config.topic, QoS::AtLeastOnce).await
+                        {
+                            log::error!("Failed to subscribe to {}: {error}", config.topic);
+                        }
+                    }
+                    Ok(rumqttc::Event::Incoming(Packet::Publish(publish))) => {
+                        match parse_notification(&publish.payload) {
+                            Ok(notification) => {
+                                let input = crate::input::Input::Notify(notification);
+                                if tx.send(Event::Input(input)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            Err(error) => log::error!(
+                                "Ignoring a notification from {}: {error}",
+                                publish.topic
+                            ),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        // polling again reconnects
+                        log::error!("Lost our MQTT connection to {}: {error}", config.host);
+                        sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            }
+        });
+
+        Ok(Mqtt { subscriber })
+    }
+
+    #[cfg(not(feature = "mqtt"))]
+    pub fn spawn(_config: &MqttConfig, _tx: &Sender<Event>) -> anyhow::Result<Mqtt> {
+        Err(anyhow::anyhow!(
+            "We were built without support for [mqtt], see our mqtt feature"
+        ))
+    }
+}
+
+impl Drop for Mqtt {
+    fn drop(&mut self) {
+        self.subscriber.abort();
+    }
+}
+
+/// A JSON notification, or anything else as the text of one with our defaults
+#[cfg(feature = "mqtt")]
+fn parse_notification(payload: &[u8]) -> anyhow::Result<crate::notification::NotificationConfig> {
+    let payload = std::str::from_utf8(payload)?.trim();
+    if payload.starts_with('{') {
+        return Ok(serde_json::from_str(payload)?);
+    }
+
+    Ok(serde_json::from_value(
+        serde_json::json!({ "text": payload }),
+    )?)
+}
+
+#[cfg(all(test, feature = "mqtt"))]
+mod tests {
+    use super::*;
+    use crate::notification::{NotificationStyle, Priority};
+
+    #[test]
+    fn takes_json_or_text() {
+        let notification =
+            parse_notification(br#"{ "text": "Washer's done", "style": "static" }"#).unwrap();
+        assert_eq!(notification.style, NotificationStyle::Static);
+
+        let notification = parse_notification(b"Someone's at the door\n").unwrap();
+        assert_eq!(notification.text, "Someone's at the door");
+        assert_eq!(notification.priority, Priority::Low);
+
+        assert!(parse_notification(br#"{ "text": "Oops", "colour": "red" }"#).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Something to show over our scenes, from our remote control, MQTT or a timer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub text: String,
+    #[serde(default)]
+    pub priority: Priority,
+    /// We're dropped if we haven't been shown by then, like `2025-07-04T09:00:00-04:00`
+    pub expires: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub style: NotificationStyle,
+    /// How many times we're shown in a row
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+/// Whether a notification interrupts what we're showing
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Priority {
+    /// Wait for our display to move on from its scene
+    #[default]
+    Low,
+    /// Interrupt our display's scene right away
+    High,
+}
+
+/// How a notification's text is shown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationStyle {
+    /// Scroll through our text, like a message
+    #[default]
+    Scroll,
+    /// Blink our text each second
+    Flash,
+    /// Hold as much of our text as fits
+    Static,
+}
+
+/// The `[notifications]` section of our config
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    /// Where we keep our queue, so it survives restarts
+    pub path: PathBuf,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            path: PathBuf::from("notifications.json"),
+        }
+    }
+}
+
+/// A notification waiting for some of our displays
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Queued {
+    notification: NotificationConfig,
+    /// The displays that haven't shown us yet
+    displays: Vec<String>,
+}
+
+/// Notifications waiting to be shown, saved to disk whenever they change
+#[derive(Debug, Default)]
+pub struct Notifications {
+    path: Option<PathBuf>,
+    queue: Vec<Queued>,
+}
+
+impl Notifications {
+    /// The queue we saved to `path`, without anything that expired while we were gone
+    pub fn load(path: &Path, now: DateTime<Utc>) -> anyhow::Result<Notifications> {
+        let queue = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(error) => return Err(error.into()),
+        };
+        let mut notifications = Notifications {
+            path: Some(path.to_path_buf()),
+            queue,
+        };
+        notifications.expire(now);
+
+        Ok(notifications)
+    }
+
+    /// An empty queue saved to `path`, for when we can't load the one that's there
+    pub fn new(path: &Path) -> Notifications {
+        Notifications {
+            path: Some(path.to_path_buf()),
+            queue: vec![],
+        }
+    }
+
+    /// Keep our queue somewhere else from now on
+    pub fn move_to(&mut self, path: &Path) {
+        if self.path.as_deref() != Some(path) {
+            self.path = Some(path.to_path_buf());
+            self.save();
+        }
+    }
+
+    /// Queue `notification` for each of `displays`
+    pub fn push(&mut self, notification: NotificationConfig, displays: Vec<String>) {
+        self.queue.push(Queued {
+            notification,
+            displays,
+        });
+        self.save();
+    }
+
+    /// Put a notification `display` was cut off from showing back at the front of its queue
+    pub fn requeue(&mut self, display: &str, notification: NotificationConfig) {
+        self.queue.insert(
+            0,
+            Queued {
+                notification,
+                displays: vec![display.to_string()],
+            },
+        );
+        self.save();
+    }
+
+    /// The most important notification (the oldest when we have a few) `display` hasn't shown
+    /// yet, at least as important as `priority`
+    pub fn take(
+        &mut self,
+        display: &str,
+        priority: Priority,
+        now: DateTime<Utc>,
+    ) -> Option<NotificationConfig> {
+        self.expire(now);
+
+        let (i, _) = self
+            .queue
+            .iter()
+            .enumerate()
+            .filter(|(_, queued)| queued.notification.priority >= priority)
+            .filter(|(_, queued)| queued.displays.iter().any(|name| name == display))
+            .min_by_key(|&(i, queued)| (Reverse(queued.notification.priority), i))?;
+
+        let queued = &mut self.queue[i];
+        queued.displays.retain(|name| name != display);
+        let notification = queued.notification.clone();
+        if queued.displays.is_empty() {
+            self.queue.remove(i);
+        }
+        self.save();
+
+        Some(notification)
+    }
+
+    /// Forget about displays we don't have anymore, so we don't wait on them forever
+    pub fn retain_displays(&mut self, displays: &[String]) {
+        let before = self.queue.clone();
+        for queued in &mut self.queue {
+            queued.displays.retain(|name| displays.contains(name));
+        }
+        self.queue.retain(|queued| !queued.displays.is_empty());
+
+        if self.queue != before {
+            self.save();
+        }
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        let before = self.queue.len();
+        self.queue.retain(|queued| {
+            let expired = queued
+                .notification
+                .expires
+                .is_some_and(|expires| expires <= now);
+            if expired {
+                log::info!("Our notification {:?} expired", queued.notification.text);
+            }
+            !expired
+        });
+
+        if self.queue.len() != before {
+            self.save();
+        }
+    }
+
+    /// Write our queue to a temporary file then move it over our old one, so we never leave a
+    /// partial queue behind
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let saved = serde_json::to_string_pretty(&self.queue)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                let temporary = path.with_extension("json.tmp");
+                fs::write(&temporary, json)?;
+                fs::rename(&temporary, path)?;
+
+                Ok(())
+            });
+        if let Err(error) = saved {
+            log::error!("Failed to save our notifications to {path:?}: {error:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::at;
+    use std::env;
+
+    fn notification(json: &str) -> NotificationConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn displays(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn important_ones_go_first() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut notifications = Notifications::default();
+        notifications.push(notification(r#"{ "text": "first" }"#), displays(&["main"]));
+        notifications.push(notification(r#"{ "text": "second" }"#), displays(&["main"]));
+        notifications.push(
+            notification(r#"{ "text": "urgent", "priority": "high", "style": "flash" }"#),
+            displays(&["main"]),
+        );
+
+        let take = |notifications: &mut Notifications, priority| {
+            notifications
+                .take("main", priority, now)
+                .map(|notification| notification.text)
+        };
+        assert_eq!(take(&mut notifications, Priority::High).unwrap(), "urgent");
+        // low priority notifications wait for a scene boundary
+        assert_eq!(take(&mut notifications, Priority::High), None);
+        assert_eq!(take(&mut notifications, Priority::Low).unwrap(), "first");
+        assert_eq!(take(&mut notifications, Priority::Low).unwrap(), "second");
+        assert_eq!(take(&mut notifications, Priority::Low), None);
+    }
+
+    #[test]
+    fn waits_for_every_display_until_it_expires() {
+        let mut notifications = Notifications::default();
+        notifications.push(
+            notification(r#"{ "text": "hi", "expires": "2024-06-01T12:00:00Z", "repeat": 3 }"#),
+            displays(&["kitchen", "hall", "attic"]),
+        );
+
+        let shown = notifications
+            .take("kitchen", Priority::Low, at("2024-06-01T11:00:00Z"))
+            .unwrap();
+        assert_eq!(shown.repeat, 3);
+        assert!(notifications
+            .take("kitchen", Priority::Low, at("2024-06-01T11:00:00Z"))
+            .is_none());
+
+        notifications.retain_displays(&displays(&["kitchen", "hall"]));
+        assert_eq!(notifications.queue[0].displays, ["hall"]);
+        assert!(notifications
+            .take("hall", Priority::Low, at("2024-06-01T12:00:00Z"))
+            .is_none());
+        assert!(notifications.queue.is_empty());
+    }
+
+    #[test]
+    fn survives_restarts() {
+        let path = env::temp_dir().join(format!("notifications-{}.json", std::process::id()));
+        let now = at("2024-06-01T12:00:00Z");
+
+        let mut notifications = Notifications::new(&path);
+        notifications.push(notification(r#"{ "text": "kept" }"#), displays(&["main"]));
+        notifications.push(
+            notification(r#"{ "text": "stale", "expires": "2024-06-01T11:00:00Z" }"#),
+            displays(&["main"]),
+        );
+
+        let mut loaded = Notifications::load(&path, now).unwrap();
+        let kept = loaded.take("main", Priority::Low, now).unwrap();
+        assert_eq!(kept, notification(r#"{ "text": "kept" }"#));
+        assert!(Notifications::load(&path, now).unwrap().queue.is_empty());
+
+        fs::remove_file(&path).unwrap();
+        assert!(Notifications::load(&path, now).unwrap().queue.is_empty());
+    }
+}
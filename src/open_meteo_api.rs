@@ -0,0 +1,80 @@
+use crate::daylight::Location;
+use crate::weather::{TemperatureUnit, WeatherConfig};
+
+use anyhow::anyhow;
+use lazy_static::lazy_static;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+lazy_static! {
+    /// Gives up on Open-Meteo rather than leaving our forecast hanging
+    static ref CLIENT: Client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Our HTTP client builds");
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherResponse {
+    pub current: CurrentWeather,
+    pub daily: DailyWeather,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CurrentWeather {
+    pub temperature_2m: f64,
+    /// A WMO weather interpretation code
+    pub weather_code: u8,
+    /// 1 during the day, 0 at night
+    pub is_day: u8,
+}
+
+/// Today's forecast, each is a list with a value for every day we asked for
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DailyWeather {
+    pub temperature_2m_max: Vec<f64>,
+    pub temperature_2m_min: Vec<f64>,
+}
+
+/// What we get back instead when our request was bad
+#[derive(Deserialize, Debug)]
+struct ErrorResponse {
+    reason: String,
+}
+
+pub async fn query_weather(
+    location: Location,
+    config: &WeatherConfig,
+) -> anyhow::Result<WeatherResponse> {
+    let unit = match config.unit {
+        TemperatureUnit::Celsius => "celsius",
+        TemperatureUnit::Fahrenheit => "fahrenheit",
+    };
+    let url = format!(
+        "{}/v1/forecast?latitude={}&longitude={}&current=temperature_2m,weather_code,is_day\
+        &daily=temperature_2m_max,temperature_2m_min&temperature_unit={unit}&timezone=auto\
+        &forecast_days=1",
+        config.base_url.trim_end_matches('/'),
+        location.latitude,
+        location.longitude,
+    );
+
+    log::debug!("Querying Weather: {url}");
+
+    let response = CLIENT.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let reason = response
+            .json::<ErrorResponse>()
+            .await
+            .map_or_else(|_| status.to_string(), |error| error.reason);
+        return Err(anyhow!("Open-Meteo said {reason}"));
+    }
+
+    let weather = response.json::<WeatherResponse>().await?;
+
+    log::debug!("API Response: {weather:?}");
+
+    Ok(weather)
+}
@@ -0,0 +1,146 @@
+//! Keeps something we fetch (a feed, our calendars, the weather) fresh in the background, so our
+//! event loop only ever reads what we already have.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+/// The longest we wait between fetches while they're failing
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 10);
+
+/// What we last fetched
+#[derive(Debug, Default)]
+pub struct Polled<T> {
+    pub value: T,
+    /// Our last fetch failed (or only got some of what we asked for), so `value` might be stale
+    pub failing: bool,
+}
+
+/// Fetches for as long as we're around
+pub struct Poller<T> {
+    polled: Arc<Mutex<Polled<T>>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl<T: Default + Send + 'static> Poller<T> {
+    /// Call `fetch` every `refresh`, backing off while it fails. A failed fetch keeps what we had.
+    pub fn spawn<F, Fut>(name: String, refresh: Duration, mut fetch: F) -> Poller<T>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<Polled<T>>> + Send + 'static,
+    {
+        let polled = Arc::new(Mutex::new(Polled::default()));
+
+        let shared = polled.clone();
+        let task = tokio::spawn(async move {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                let failing = match fetch().await {
+                    Ok(fetched) => {
+                        let failing = fetched.failing;
+                        *shared.lock().expect("Our poller isn't poisoned") = fetched;
+                        failing
+                    }
+                    Err(error) => {
+                        log::error!("Error getting {name}: {error}");
+                        shared.lock().expect("Our poller isn't poisoned").failing = true;
+                        true
+                    }
+                };
+
+                let wait = if failing {
+                    backoff = MAX_BACKOFF.min(backoff * 2);
+                    log::info!("Trying to get {name} again in {}s", backoff.as_secs());
+                    backoff
+                } else {
+                    backoff = Duration::from_secs(1);
+                    refresh
+                };
+                sleep(wait).await;
+            }
+        });
+
+        Poller {
+            polled,
+            task: Some(task),
+        }
+    }
+}
+
+impl<T> Poller<T> {
+    /// Something we already fetched, without polling for more
+    #[cfg(test)]
+    pub fn fixed(value: T) -> Poller<T> {
+        Poller {
+            polled: Arc::new(Mutex::new(Polled {
+                value,
+                failing: false,
+            })),
+            task: None,
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Polled<T>> {
+        self.polled.lock().expect("Our poller isn't poisoned")
+    }
+}
+
+impl<T> Drop for Poller<T> {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::wait_until;
+    use anyhow::anyhow;
+
+    async fn wait_for<T>(poller: &Poller<T>, done: impl Fn(&Polled<T>) -> bool) {
+        wait_until(|| done(&poller.lock())).await;
+    }
+
+    #[tokio::test]
+    async fn keeps_what_it_fetched() {
+        let mut fetches = 0;
+        let poller = Poller::spawn("a count".to_string(), Duration::ZERO, move || {
+            fetches += 1;
+            async move {
+                Ok(Polled {
+                    value: fetches,
+                    failing: false,
+                })
+            }
+        });
+
+        wait_for(&poller, |polled| polled.value >= 3).await;
+        assert!(!poller.lock().failing);
+    }
+
+    #[tokio::test]
+    async fn failing_keeps_what_we_had() {
+        let mut fetched = false;
+        let poller = Poller::spawn("once".to_string(), Duration::ZERO, move || {
+            let first = !fetched;
+            fetched = true;
+            async move {
+                if !first {
+                    return Err(anyhow!("We're offline"));
+                }
+
+                Ok(Polled {
+                    value: vec![1],
+                    failing: false,
+                })
+            }
+        });
+
+        wait_for(&poller, |polled| polled.failing).await;
+        assert_eq!(poller.lock().value, [1]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Control us over HTTP, for home automation and the like:
+//! - `POST /timers` with a JSON timer (like a `[[timer]]` table) starts it, unless it'd `publish`
+//! - `POST /timers/{name}/start` starts (or restarts) one of our config's timers with `minutes`
+//! - `DELETE /timers/{name}` cancels a timer
+//! - `POST /notifications` with a JSON notification queues it for all of our displays
+
+use crate::Event;
+
+use serde::Deserialize;
+use std::net::SocketAddr;
+use tokio::sync::mpsc::Sender;
+use tokio::task::JoinHandle;
+
+/// The `[remote]` section of our config. It parses in every build, but serving it needs our
+/// `remote` feature.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteConfig {
+    /// Where we listen, like `0.0.0.0:8080`
+    pub address: SocketAddr,
+}
+
+/// Serves our remote control for as long as we're around
+#[cfg_attr(not(feature = "remote"), allow(dead_code))]
+pub struct Remote {
+    #[cfg(all(test, feature = "remote"))]
+    address: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl Remote {
+    #[cfg(feature = "remote")]
+    pub fn spawn(config: &RemoteConfig, tx: &Sender<Event>) -> anyhow::Result<Remote> {
+        use axum::routing::{delete, post};
+        use axum::Router;
+
+        // bind right away so a bad address fails our startup
+        let listener = std::net::TcpListener::bind(config.address)?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let listener = tokio::net::TcpListener::from_std(listener)?;
+        let app = Router::new()
+            .route("/timers", post(routes::add_timer))
+            .route("/timers/{name}/start", post(routes::start_timer))
+            .route("/timers/{name}", delete(routes::cancel_timer))
+            .route("/notifications", post(routes::notify))
+            .with_state(tx.clone());
+        let server = tokio::spawn(async move {
+            if let Err(error) = axum::serve(listener, app).await {
+                log::error!("Our remote control stopped: {error}");
+            }
+        });
+
+        log::info!("Serving our remote control on http://{address}");
+        Ok(Remote {
+            #[cfg(all(test, feature = "remote"))]
+            address,
+            server,
+        })
+    }
+
+    #[cfg(not(feature = "remote"))]
+    pub fn spawn(_config: &RemoteConfig, _tx: &Sender<Event>) -> anyhow::Result<Remote> {
+        Err(anyhow::anyhow!(
+            "We were built without support for our [remote], see our remote feature"
+        ))
+    }
+
+    /// Where we're actually listening, which only differs from our config's address for port 0
+    #[cfg(all(test, feature = "remote"))]
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+#[cfg(feature = "remote")]
+mod routes {
+    use crate::input::Input;
+    use crate::notification::NotificationConfig;
+    use crate::timer::TimerConfig;
+    use crate::Event;
+
+    use axum::extract::{Path, State};
+    use axum::http::StatusCode;
+    use axum::Json;
+    use tokio::sync::mpsc::Sender;
+
+    /// Our main loop applies our inputs, so we can't say whether they worked, only that we got them
+    async fn send(tx: &Sender<Event>, input: Input) -> StatusCode {
+        log::debug!("Remote {input:?}");
+        match tx.send(Event::Input(input)).await {
+            Ok(()) => StatusCode::ACCEPTED,
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub async fn add_timer(
+        State(tx): State<Sender<Event>>,
+        Json(config): Json<TimerConfig>,
+    ) -> StatusCode {
+        if config.publishes() {
+            log::warn!("Refusing our remote's timer {}, it publishes", config.name);
+            return StatusCode::FORBIDDEN;
+        }
+
+        send(&tx, Input::AddTimer(config)).await
+    }
+
+    pub async fn start_timer(
+        State(tx): State<Sender<Event>>,
+        Path(name): Path<String>,
+    ) -> StatusCode {
+        send(&tx, Input::StartTimer(name)).await
+    }
+
+    pub async fn cancel_timer(
+        State(tx): State<Sender<Event>>,
+        Path(name): Path<String>,
+    ) -> StatusCode {
+        send(&tx, Input::CancelTimer(name)).await
+    }
+
+    pub async fn notify(
+        State(tx): State<Sender<Event>>,
+        Json(notification): Json<NotificationConfig>,
+    ) -> StatusCode {
+        send(&tx, Input::Notify(notification)).await
+    }
+}
+
+#[cfg(all(test, feature = "remote"))]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+    use crate::timer::TimerConfig;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn turns_requests_into_inputs() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let config = RemoteConfig {
+            address: "127.0.0.1:0".parse().unwrap(),
+        };
+        let remote = Remote::spawn(&config, &tx).unwrap();
+        let url = format!("http://{}", remote.address());
+        let client = reqwest::Client::new();
+
+        let started = client
+            .post(format!("{url}/timers/pomodoro/start"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(started.status(), reqwest::StatusCode::ACCEPTED);
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::Input(Input::StartTimer(name))) if name == "pomodoro"
+        ));
+
+        client
+            .post(format!("{url}/timers"))
+            .json(&serde_json::json!({ "name": "tea", "minutes": 4, "done": { "type": "flash" } }))
+            .send()
+            .await
+            .unwrap();
+        let Some(Event::Input(Input::AddTimer(added))) = rx.recv().await else {
+            panic!("We didn't add our timer");
+        };
+        assert_eq!(
+            added,
+            TimerConfig {
+                name: "tea".to_string(),
+                at: None,
+                minutes: Some(4),
+                icon: None,
+                format: Default::default(),
+                done: Default::default(),
+            }
+        );
+
+        // only our config can make us POST somewhere
+        let publishing = client
+            .post(format!("{url}/timers"))
+            .json(&serde_json::json!({
+                "name": "tea",
+                "minutes": 4,
+                "done": { "type": "publish", "url": "http://127.0.0.1:8123/" }
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(publishing.status(), reqwest::StatusCode::FORBIDDEN);
+
+        // timers need a name
+        let invalid = client
+            .post(format!("{url}/timers"))
+            .json(&serde_json::json!({ "minutes": 4 }))
+            .send()
+            .await
+            .unwrap();
+        assert!(invalid.status().is_client_error());
+
+        client
+            .delete(format!("{url}/timers/tea"))
+            .send()
+            .await
+            .unwrap();
+        assert!(matches!(
+            rx.recv().await,
+            Some(Event::Input(Input::CancelTimer(name))) if name == "tea"
+        ));
+
+        client
+            .post(format!("{url}/notifications"))
+            .json(&serde_json::json!({ "text": "Tea's ready", "priority": "high", "repeat": 2 }))
+            .send()
+            .await
+            .unwrap();
+        let Some(Event::Input(Input::Notify(notification))) = rx.recv().await else {
+            panic!("We didn't queue our notification");
+        };
+        assert_eq!(notification.text, "Tea's ready");
+        assert_eq!(notification.repeat, 2);
+    }
+}
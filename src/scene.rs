@@ -0,0 +1,202 @@
+mod calendar;
+mod clock;
+mod daylight;
+mod flash;
+mod layout;
+mod message;
+mod notification;
+mod subway;
+mod timer;
+mod weather;
+
+pub use calendar::{CalendarConfig, CalendarScene};
+pub use clock::{ClockConfig, ClockScene};
+pub use daylight::DaylightScene;
+pub use flash::FlashScene;
+pub use layout::LayoutScene;
+pub use message::MessageScene;
+pub use notification::NotificationScene;
+pub use subway::{SubwayConfig, SubwayScene};
+pub use timer::{TimerScene, TimerSceneConfig};
+pub use weather::{WeatherScene, WeatherSceneConfig};
+
+use crate::daylight::Daylight;
+use crate::dot_matrix::DotMatrix;
+use crate::grayscale::Grayscale;
+use crate::seven_segment::SevenSegment;
+use crate::timer::Timers;
+use crate::weather::Weather;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Timelike, Utc};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Everything a scene can draw from
+pub struct Context<'a> {
+    pub now: DateTime<Utc>,
+    pub daylight: &'a Daylight,
+    pub weather: &'a Weather,
+    pub timers: &'a Timers,
+}
+
+/// Our colons blink every second
+pub fn show_colon(context: &Context) -> bool {
+    context.now.second().is_multiple_of(2)
+}
+
+/// One screen worth of content. Our targets are cleared before we're asked to draw.
+pub trait Scene {
+    fn name(&self) -> &str;
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()>;
+
+    /// Scenes that don't care about gray levels get their binary frame copied over
+    fn draw_gray(
+        &mut self,
+        context: &Context,
+        matrix: &mut DotMatrix,
+        grayscale: &mut Grayscale,
+    ) -> anyhow::Result<()> {
+        self.draw(context, matrix)?;
+        grayscale.load(matrix);
+
+        Ok(())
+    }
+
+    /// Whether we're moving at `now`, so we're worth drawing more often than once a second
+    fn is_scrolling(&self, _now: DateTime<Utc>) -> bool {
+        false
+    }
+
+    /// 7-segment displays can't show much, so by default we just show our name
+    fn draw_segments(
+        &mut self,
+        _context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        segments.write_str(self.name());
+
+        Ok(())
+    }
+}
+
+/// A scene in a display's playlist
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum SceneConfig {
+    Daylight,
+    Clock(ClockConfig),
+    Weather(WeatherSceneConfig),
+    /// The next trains at a station, from a GTFS-realtime feed
+    Subway(SubwayConfig),
+    /// Counts down to our next event, from ICS feeds
+    Calendar(CalendarConfig),
+    /// Counts down to one of our `[[timer]]`s
+    Timer(TimerSceneConfig),
+    /// Widgets from a layout file, see `layouts/daylight.toml`
+    Layout {
+        path: PathBuf,
+    },
+}
+
+impl SceneConfig {
+    pub fn open(&self) -> anyhow::Result<Box<dyn Scene>> {
+        Ok(match self {
+            SceneConfig::Daylight => Box::new(DaylightScene),
+            SceneConfig::Clock(config) => Box::new(ClockScene::new(config.clone())?),
+            SceneConfig::Weather(config) => Box::new(WeatherScene::new(config.clone())?),
+            SceneConfig::Subway(config) => Box::new(SubwayScene::open(config.clone())?),
+            SceneConfig::Calendar(config) => Box::new(CalendarScene::open(config.clone())?),
+            SceneConfig::Timer(config) => Box::new(TimerScene::new(config.clone())),
+            SceneConfig::Layout { path } => Box::new(LayoutScene::open(path)?),
+        })
+    }
+
+    /// The file we're loaded from, if we have one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SceneConfig::Daylight
+            | SceneConfig::Clock(_)
+            | SceneConfig::Weather(_)
+            | SceneConfig::Subway(_)
+            | SceneConfig::Calendar(_)
+            | SceneConfig::Timer(_) => None,
+            SceneConfig::Layout { path } => Some(path),
+        }
+    }
+}
+
+/// The scenes we flip between
+pub struct Playlist {
+    scenes: Vec<Box<dyn Scene>>,
+    current: usize,
+}
+
+impl Playlist {
+    pub fn new(scenes: Vec<Box<dyn Scene>>) -> anyhow::Result<Playlist> {
+        if scenes.is_empty() {
+            return Err(anyhow!("Our playlist needs at least 1 scene"));
+        }
+
+        Ok(Playlist { scenes, current: 0 })
+    }
+
+    pub fn open(configs: &[SceneConfig]) -> anyhow::Result<Playlist> {
+        Playlist::new(
+            configs
+                .iter()
+                .map(SceneConfig::open)
+                .collect::<anyhow::Result<_>>()?,
+        )
+    }
+
+    /// Swap in `playlist`'s scenes, staying on the same one if we still have it
+    pub fn replace(&mut self, playlist: Playlist) {
+        let current = self.current.min(playlist.scenes.len() - 1);
+        *self = Playlist {
+            current,
+            ..playlist
+        };
+    }
+
+    /// Whether we only have 1 scene, so we can't move on from it
+    pub fn is_single(&self) -> bool {
+        self.scenes.len() == 1
+    }
+
+    pub fn current(&mut self) -> &mut dyn Scene {
+        self.scenes[self.current].as_mut()
+    }
+
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.scenes.len();
+        log::info!("Showing {}", self.current().name());
+    }
+
+    pub fn previous(&mut self) {
+        self.current = (self.current + self.scenes.len() - 1) % self.scenes.len();
+        log::info!("Showing {}", self.current().name());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(scenes: usize) -> Playlist {
+        Playlist::new((0..scenes).map(|_| Box::new(DaylightScene) as _).collect()).unwrap()
+    }
+
+    #[test]
+    fn reloading_keeps_our_place() {
+        let mut scenes = playlist(3);
+        scenes.next();
+        scenes.next();
+
+        scenes.replace(playlist(4));
+        assert_eq!(scenes.current, 2);
+        scenes.replace(playlist(1));
+        assert_eq!(scenes.current, 0);
+    }
+}
@@ -0,0 +1,446 @@
+use crate::calendar::{query_calendar, Calendar, Occurrence};
+use crate::canvas::{Canvas, Pan};
+use crate::daylight::until;
+use crate::dot_matrix::{DotMatrix, DISPLAY_HEIGHT, ROW_LENGTH};
+use crate::font::{BitmapFont, FontStyle, NARROW_4X7, PROPORTIONAL_8, TINY_3X5};
+use crate::layout::{countdown_width, draw_countdown};
+use crate::poller::{Polled, Poller};
+use crate::scene::{show_colon, Context, Scene};
+use crate::seven_segment::SevenSegment;
+use crate::sprite::Sprite;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use embedded_graphics::geometry::Point;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How fast titles too long for our display scroll by
+const SCROLL_PIXELS_PER_SECOND: i64 = 6;
+
+/// How long we hold still at each end of a scrolling title
+const SCROLL_PAUSE: chrono::Duration = chrono::Duration::seconds(1);
+
+/// Our `{ type = "calendar" }` scenes
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// ICS URLs (http, https or webcal, like a CalDAV calendar's export link) or `.ics` files
+    pub calendars: Vec<String>,
+    /// For all-day and floating events, like the clock's (our system's time zone by default)
+    pub time_zone: Option<Tz>,
+    /// How often we fetch our calendars
+    #[serde(default = "default_refresh_minutes")]
+    pub refresh_minutes: u64,
+    /// How long we show our countdown before our next event's title
+    #[serde(default = "default_cycle_seconds")]
+    pub cycle_seconds: u32,
+}
+
+fn default_refresh_minutes() -> u64 {
+    5
+}
+
+fn default_cycle_seconds() -> u32 {
+    5
+}
+
+/// What we're showing right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Countdown,
+    /// Our title started showing at this time
+    Title(DateTime<Utc>),
+}
+
+/// Counts down to our next event, alternating with its (scrolling) title
+pub struct CalendarScene {
+    config: CalendarConfig,
+    /// From a day before our last fetch, for events that are still going on, to 2 weeks after it
+    agenda: Poller<Vec<Occurrence>>,
+}
+
+impl CalendarScene {
+    /// Start polling our calendars in the background
+    pub fn open(config: CalendarConfig) -> anyhow::Result<CalendarScene> {
+        if config.calendars.is_empty() {
+            return Err(anyhow!("Our calendar scene needs at least 1 calendar"));
+        }
+        if config.refresh_minutes == 0 || config.cycle_seconds == 0 {
+            return Err(anyhow!(
+                "Our calendar's refresh_minutes and cycle_seconds need to be at least 1"
+            ));
+        }
+
+        // each calendar's last good copy, so one failing doesn't hide the others' events
+        let calendars = Arc::new(Mutex::new(vec![None; config.calendars.len()]));
+        let (sources, time_zone) = (config.calendars.clone(), config.time_zone);
+        let agenda = Poller::spawn(
+            "our calendars".to_string(),
+            Duration::from_secs(config.refresh_minutes * 60),
+            move || fetch(sources.clone(), time_zone, calendars.clone()),
+        );
+
+        Ok(CalendarScene { config, agenda })
+    }
+
+    /// Our next event that hasn't ended yet, and whether we might be missing some. All-day events
+    /// that already started (like a holiday) only count once nothing else is coming up.
+    fn next(&self, now: DateTime<Utc>) -> (Option<Occurrence>, bool) {
+        let agenda = self.agenda.lock();
+        let next = agenda
+            .value
+            .iter()
+            .filter(|occurrence| occurrence.end > now)
+            .min_by_key(|occurrence| {
+                (
+                    occurrence.all_day && occurrence.start <= now,
+                    occurrence.start,
+                )
+            })
+            .cloned();
+
+        (next, agenda.failing)
+    }
+
+    /// Loops from the unix epoch like our clock does, staying on our title long enough to scroll
+    /// all of it
+    fn page(&self, now: DateTime<Utc>, title: &str) -> Page {
+        let countdown = chrono::Duration::seconds(self.config.cycle_seconds as i64);
+        let title_length = countdown.max(scroll_duration(title) + SCROLL_PAUSE * 2);
+        let period = (countdown + title_length).num_milliseconds();
+
+        let into = chrono::Duration::milliseconds(now.timestamp_millis().rem_euclid(period));
+        if into < countdown {
+            Page::Countdown
+        } else {
+            Page::Title(now - (into - countdown))
+        }
+    }
+
+    /// Our wall clock time, for events too far away to count down to
+    fn local(&self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self.config.time_zone {
+            Some(time_zone) => time.with_timezone(&time_zone).naive_local(),
+            None => time.with_timezone(&Local).naive_local(),
+        }
+    }
+}
+
+/// Our calendars' occurrences, with the last good copy of any that fail
+async fn fetch(
+    sources: Vec<String>,
+    time_zone: Option<Tz>,
+    calendars: Arc<Mutex<Vec<Option<Calendar>>>>,
+) -> anyhow::Result<Polled<Vec<Occurrence>>> {
+    let mut fetched = vec![];
+    for source in &sources {
+        fetched.push(query_calendar(source).await.inspect_err(|error| {
+            log::error!("Error getting calendar {source}: {error}");
+        }));
+    }
+
+    let mut calendars = calendars.lock().expect("Our calendars aren't poisoned");
+    let mut failing = false;
+    for (calendar, fetched) in calendars.iter_mut().zip(fetched) {
+        match fetched {
+            Ok(fetched) => *calendar = Some(fetched),
+            Err(_) => failing = true,
+        }
+    }
+
+    let mut merged = Calendar::default();
+    for calendar in calendars.iter().flatten() {
+        merged.extend(calendar.clone());
+    }
+    let now = Utc::now();
+    let occurrences = merged.occurrences(
+        time_zone,
+        now - chrono::Duration::days(1),
+        now + chrono::Duration::days(14),
+    );
+
+    Ok(Polled {
+        value: occurrences,
+        failing,
+    })
+}
+
+/// Events without a summary are just busy
+fn title(occurrence: &Occurrence) -> &str {
+    if occurrence.summary.trim().is_empty() {
+        "Busy"
+    } else {
+        occurrence.summary.trim()
+    }
+}
+
+/// How long scrolling `title` across our display takes, if it doesn't fit
+fn scroll_duration(title: &str) -> chrono::Duration {
+    let overflow = PROPORTIONAL_8
+        .tight_width(title)
+        .saturating_sub(ROW_LENGTH as u32);
+    chrono::Duration::milliseconds(overflow as i64 * 1000 / SCROLL_PIXELS_PER_SECOND)
+}
+
+fn draw_text(
+    font: &BitmapFont,
+    text: &str,
+    top_left: Point,
+    matrix: &mut DotMatrix,
+) -> anyhow::Result<()> {
+    let style = FontStyle::new(font, BinaryColor::On);
+    Text::with_baseline(text, top_left, style, Baseline::Top).draw(matrix)?;
+
+    Ok(())
+}
+
+/// `text` in our narrow font, right aligned after a tiny `label`
+fn draw_labelled(label: &str, text: &str, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+    let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width(text) as i32;
+    draw_text(&NARROW_4X7, text, Point::new(left, 0), matrix)?;
+    // our label sits on our text's baseline, if there's room
+    if (TINY_3X5.tight_width(label) as i32) < left {
+        let top = (NARROW_4X7.height() - TINY_3X5.height()) as i32;
+        draw_text(&TINY_3X5, label, Point::new(0, top), matrix)?;
+    }
+
+    Ok(())
+}
+
+impl Scene for CalendarScene {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        let now = context.now;
+        let (next, failing) = self.next(now);
+        let Some(next) = next else {
+            if failing {
+                Sprite::builtin("wifi-error")?.draw(now, Point::zero(), matrix)?;
+            }
+            let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width("--") as i32;
+            return draw_text(&NARROW_4X7, "--", Point::new(left, 0), matrix);
+        };
+        let title = title(&next);
+
+        match self.page(now, title) {
+            Page::Countdown if next.start <= now && next.all_day => {
+                draw_labelled("", "TODAY", matrix)
+            }
+            Page::Countdown if next.start <= now => draw_labelled("", "NOW", matrix),
+            Page::Countdown if next.start - now >= chrono::Duration::days(1) => {
+                let date = self.local(next.start).format("%b %-d");
+                draw_labelled("ON", &date.to_string().to_uppercase(), matrix)
+            }
+            Page::Countdown => {
+                let countdown = until(next.start - now);
+                let left = ROW_LENGTH as i32 - countdown_width(&NARROW_4X7, countdown) as i32;
+                draw_countdown(
+                    &NARROW_4X7,
+                    countdown,
+                    show_colon(context),
+                    Point::new(left, 0),
+                    matrix,
+                )?;
+                let top = (NARROW_4X7.height() - TINY_3X5.height()) as i32;
+                draw_text(&TINY_3X5, "IN", Point::new(0, top), matrix)
+            }
+            Page::Title(started) => {
+                let width = PROPORTIONAL_8.tight_width(title) as usize;
+                let mut canvas = Canvas::new(width.max(ROW_LENGTH), DISPLAY_HEIGHT);
+                // short titles are centered
+                let left = ROW_LENGTH.saturating_sub(width) as i32 / 2;
+                let style = FontStyle::new(&PROPORTIONAL_8, BinaryColor::On);
+                Text::with_baseline(title, Point::new(left, 0), style, Baseline::Top)
+                    .draw(&mut canvas)?;
+
+                let mut viewport = canvas.viewport();
+                Pan::new(
+                    Point::zero(),
+                    viewport.max_position(),
+                    started + SCROLL_PAUSE,
+                    scroll_duration(title),
+                )
+                .apply(&mut viewport, now);
+                canvas.show(&viewport, matrix);
+
+                Ok(())
+            }
+        }
+    }
+
+    fn is_scrolling(&self, now: DateTime<Utc>) -> bool {
+        let Some(next) = self.next(now).0 else {
+            return false;
+        };
+        let title = title(&next);
+
+        matches!(self.page(now, title), Page::Title(_))
+            && scroll_duration(title) > chrono::Duration::zero()
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let now = context.now;
+        match self.next(now).0 {
+            None => segments.write_str("--"),
+            Some(next) if next.start <= now => segments.write_str("now"),
+            Some(next) if next.start - now >= chrono::Duration::days(1) => {
+                segments.write_str_right(&self.local(next.start).format("%m-%d").to_string())
+            }
+            Some(next) => {
+                let (h, m) = until(next.start - now);
+                segments.write_clock(h, m, show_colon(context));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::scene::SceneConfig;
+    use crate::testing::{assert_golden, at, render_scene, wait_until};
+    use std::env;
+    use std::fs;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+                       BEGIN:VEVENT\r\n\
+                       SUMMARY:Holiday\r\n\
+                       DTSTART;VALUE=DATE:20240603\r\n\
+                       END:VEVENT\r\n\
+                       BEGIN:VEVENT\r\n\
+                       SUMMARY:Standup\r\n\
+                       DTSTART;TZID=America/New_York:20240101T093000\r\n\
+                       DURATION:PT15M\r\n\
+                       RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n\
+                       END:VEVENT\r\n\
+                       BEGIN:VEVENT\r\n\
+                       SUMMARY:Quarterly planning with everyone\r\n\
+                       DTSTART:20240607T180000Z\r\n\
+                       DTEND:20240607T190000Z\r\n\
+                       END:VEVENT\r\n\
+                       END:VCALENDAR\r\n";
+
+    fn config(calendar: &str) -> CalendarConfig {
+        let toml = format!(
+            r#"
+            type = "calendar"
+            calendars = ["{calendar}"]
+            time_zone = "America/New_York"
+            "#
+        );
+        match toml::from_str(&toml).unwrap() {
+            SceneConfig::Calendar(config) => config,
+            config => panic!("Not a calendar: {config:?}"),
+        }
+    }
+
+    /// A scene that already knows about our calendar, without polling anything
+    fn scene(ics: &str, now: DateTime<Utc>) -> CalendarScene {
+        let config = config("calendar.ics");
+        let occurrences = Calendar::from_ics(ics).unwrap().occurrences(
+            config.time_zone,
+            now - chrono::Duration::days(1),
+            now + chrono::Duration::days(14),
+        );
+        CalendarScene {
+            config,
+            agenda: Poller::fixed(occurrences),
+        }
+    }
+
+    fn render(scene: &mut CalendarScene, now: DateTime<Utc>) -> crate::snapshot::Snapshot {
+        render_scene(scene, Clock::Fixed(now), &Daylight::default())
+    }
+
+    #[test]
+    fn counts_down_to_our_next_meeting() {
+        // Monday at 9:18 in New York, during a holiday, with standup in 12 minutes
+        let now = at("2024-06-03T13:18:00Z");
+        let mut scene = scene(ICS, now);
+        assert_eq!(scene.next(now).0.unwrap().summary, "Standup");
+
+        assert_golden("calendar_countdown", &render(&mut scene, now));
+        assert_golden(
+            "calendar_title",
+            &render(&mut scene, now + chrono::Duration::seconds(5)),
+        );
+    }
+
+    #[test]
+    fn scrolls_long_titles() {
+        // Friday after standup, with planning this afternoon
+        let now = at("2024-06-07T14:00:00Z");
+        let mut scene = scene(ICS, now);
+        let title = "Quarterly planning with everyone";
+        assert_eq!(scene.next(now).0.unwrap().summary, title);
+
+        let Page::Title(started) = scene.page(now + chrono::Duration::seconds(5), title) else {
+            panic!("Not on our title");
+        };
+        assert_golden(
+            "calendar_title_scrolled",
+            &render(&mut scene, started + SCROLL_PAUSE + scroll_duration(title)),
+        );
+    }
+
+    #[test]
+    fn shows_the_date_of_far_off_events() {
+        // Friday after planning, so standup on Monday is next
+        let now = at("2024-06-07T20:00:00Z");
+        let mut scene = scene(ICS, now);
+
+        assert_golden("calendar_date", &render(&mut scene, now));
+    }
+
+    #[test]
+    fn all_day_events_wait_their_turn() {
+        // after Monday's standup, during our holiday
+        let now = at("2024-06-03T20:00:00Z");
+        let (next, _) = scene(ICS, now).next(now);
+        assert_eq!(next.unwrap().summary, "Standup");
+
+        let holiday =
+            "BEGIN:VEVENT\r\nSUMMARY:Holiday\r\nDTSTART;VALUE=DATE:20240603\r\nEND:VEVENT\r\n";
+        let (next, _) = scene(holiday, now).next(now);
+        assert_eq!(next.unwrap().summary, "Holiday");
+    }
+
+    #[tokio::test]
+    async fn polls_our_calendars_from_files() {
+        let now = Utc::now();
+        let path = env::temp_dir().join(format!("calendar-{}.ics", std::process::id()));
+        let start = (now + chrono::Duration::hours(1)).format("%Y%m%dT%H%M%SZ");
+        let ics = format!(
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Lunch\r\nDTSTART:{start}\r\n\
+             END:VEVENT\r\nEND:VCALENDAR\r\n"
+        );
+        fs::write(&path, ics).unwrap();
+
+        let mut config = config(path.to_str().unwrap());
+        // a missing calendar doesn't hide the others
+        config.calendars.push("missing.ics".to_string());
+        let scene = CalendarScene::open(config).unwrap();
+        wait_until(|| scene.next(now).0.is_some()).await;
+        fs::remove_file(&path).unwrap();
+
+        let (next, failing) = scene.next(now);
+        assert_eq!(next.unwrap().summary, "Lunch");
+        assert!(failing);
+    }
+}
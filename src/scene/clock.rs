@@ -0,0 +1,327 @@
+use crate::dot_matrix::{DotMatrix, ROW_LENGTH};
+use crate::font::{BitmapFont, FontStyle, NARROW_4X7, TINY_3X5};
+use crate::scene::{show_colon, Context, Scene};
+use crate::seven_segment::SevenSegment;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use embedded_graphics::geometry::Point;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+
+/// Our `{ type = "clock" }` scenes
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ClockConfig {
+    /// An IANA time zone like `America/New_York`, our system's by default
+    pub time_zone: Option<Tz>,
+    #[serde(default)]
+    pub twelve_hour: bool,
+    #[serde(default)]
+    pub seconds: bool,
+    /// Blink our colon every second
+    #[serde(default = "default_blink")]
+    pub blink: bool,
+    /// Alternate our time with its date
+    #[serde(default)]
+    pub date: bool,
+    /// A world clock, cycling through these instead of our `time_zone`
+    #[serde(default)]
+    pub cities: Vec<City>,
+    /// How long we show each time (or date) before moving on
+    #[serde(default = "default_cycle_seconds")]
+    pub cycle_seconds: u32,
+}
+
+fn default_blink() -> bool {
+    true
+}
+
+fn default_cycle_seconds() -> u32 {
+    5
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct City {
+    /// A short label like `NY`, shown on our left when it fits (3 characters only fit next to a
+    /// 12 hour time)
+    pub name: String,
+    pub time_zone: Tz,
+}
+
+/// What we're showing right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Time,
+    Date,
+}
+
+/// The current time somewhere, or a few places in turn
+pub struct ClockScene {
+    config: ClockConfig,
+}
+
+impl ClockScene {
+    pub fn new(config: ClockConfig) -> anyhow::Result<ClockScene> {
+        if config.cycle_seconds == 0 {
+            return Err(anyhow!("Our clock's cycle_seconds needs to be at least 1"));
+        }
+
+        Ok(ClockScene { config })
+    }
+
+    /// The city (if we're a world clock) and page we show at `now`, which loops from the unix
+    /// epoch like our sprites do so every display agrees
+    fn page(&self, now: DateTime<Utc>) -> (Option<&City>, Page) {
+        let pages_per_city = if self.config.date { 2 } else { 1 };
+        let pages = self.config.cities.len().max(1) * pages_per_city;
+        let page = now
+            .timestamp()
+            .div_euclid(self.config.cycle_seconds as i64)
+            .rem_euclid(pages as i64) as usize;
+
+        let shown = if page.is_multiple_of(pages_per_city) {
+            Page::Time
+        } else {
+            Page::Date
+        };
+        (self.config.cities.get(page / pages_per_city), shown)
+    }
+
+    /// Our wall clock time in `city`, or in our own time zone
+    fn local(&self, city: Option<&City>, now: DateTime<Utc>) -> NaiveDateTime {
+        match city.map(|city| city.time_zone).or(self.config.time_zone) {
+            Some(time_zone) => now.with_timezone(&time_zone).naive_local(),
+            None => now.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// Our hours as we show them, and whether it's `AM` or `PM` on a 12 hour clock
+    fn hours(&self, time: &NaiveDateTime) -> (String, Option<&'static str>) {
+        if self.config.twelve_hour {
+            let (pm, hours) = time.hour12();
+            (hours.to_string(), Some(if pm { "PM" } else { "AM" }))
+        } else {
+            (format!("{:02}", time.hour()), None)
+        }
+    }
+
+    /// Right aligned `HH:MM` with our seconds after it in a smaller font, and our city or `AM`/`PM`
+    /// on our left if there's room
+    fn draw_time(
+        &self,
+        context: &Context,
+        city: Option<&City>,
+        time: &NaiveDateTime,
+        matrix: &mut DotMatrix,
+    ) -> anyhow::Result<()> {
+        let (hours, meridiem) = self.hours(time);
+        let minutes = format!("{:02}", time.minute());
+        let seconds = format!("{:02}", time.second());
+
+        let mut width = NARROW_4X7.tight_width(&format!("{hours}:{minutes}"));
+        if self.config.seconds {
+            width += 1 + TINY_3X5.tight_width(&seconds);
+        }
+        let left = ROW_LENGTH as i32 - width as i32;
+        // our smaller text sits on the same baseline as our time
+        let small_top = (NARROW_4X7.height() - TINY_3X5.height()) as i32;
+
+        let style = FontStyle::new(&NARROW_4X7, BinaryColor::On);
+        let colon =
+            Text::with_baseline(&hours, Point::new(left, 0), style, Baseline::Top).draw(matrix)?;
+        if !self.config.blink || show_colon(context) {
+            Text::with_baseline(":", colon, style, Baseline::Top).draw(matrix)?;
+        }
+        let minutes_at = colon + Point::new(NARROW_4X7.text_width(":") as i32, 0);
+        let end = Text::with_baseline(&minutes, minutes_at, style, Baseline::Top).draw(matrix)?;
+        if self.config.seconds {
+            // tight up against our minutes, which already end with a space
+            let seconds_at = Point::new(end.x, small_top);
+            draw_small(&seconds, seconds_at, matrix)?;
+        }
+
+        let label = city.map(|city| city.name.as_str()).or(meridiem);
+        if let Some(label) = label {
+            // leave a column between our label and our time
+            if (TINY_3X5.tight_width(label) as i32) < left {
+                draw_small(label, Point::new(0, small_top), matrix)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn draw_small(text: &str, top_left: Point, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+    draw_text(&TINY_3X5, text, top_left, matrix)
+}
+
+fn draw_text(
+    font: &BitmapFont,
+    text: &str,
+    top_left: Point,
+    matrix: &mut DotMatrix,
+) -> anyhow::Result<()> {
+    let style = FontStyle::new(font, BinaryColor::On);
+    Text::with_baseline(text, top_left, style, Baseline::Top).draw(matrix)?;
+
+    Ok(())
+}
+
+impl Scene for ClockScene {
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        let (city, page) = self.page(context.now);
+        let time = self.local(city, context.now);
+
+        match page {
+            Page::Time => self.draw_time(context, city, &time, matrix),
+            Page::Date => {
+                let date = time.format("%b %-d").to_string().to_uppercase();
+                let left = (ROW_LENGTH as i32 - NARROW_4X7.tight_width(&date) as i32) / 2;
+                draw_text(&NARROW_4X7, &date, Point::new(left, 0), matrix)
+            }
+        }
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let (city, page) = self.page(context.now);
+        let time = self.local(city, context.now);
+
+        match page {
+            Page::Time => {
+                let (hours, _) = self.hours(&time);
+                let separator = if !self.config.blink || show_colon(context) {
+                    "."
+                } else {
+                    ""
+                };
+                let mut text = format!("{hours}{separator}{:02}", time.minute());
+                if self.config.seconds && segments.len() >= 6 {
+                    text += &format!("{separator}{:02}", time.second());
+                }
+                segments.write_str_right(&text);
+            }
+            // Code-B can only show digits and a few letters
+            Page::Date => segments.write_str_right(&time.format("%m-%d").to_string()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::scene::SceneConfig;
+    use crate::testing::{assert_golden, at, render_scene};
+
+    /// Parsed the way our display configs are
+    fn clock(toml: &str) -> ClockScene {
+        match toml::from_str(&format!("type = \"clock\"\n{toml}")).unwrap() {
+            SceneConfig::Clock(config) => ClockScene::new(config).unwrap(),
+            config => panic!("We parsed {config:?}"),
+        }
+    }
+
+    #[test]
+    fn in_our_time_zone_with_seconds() {
+        let mut scene = clock(
+            r#"
+            time_zone = "America/New_York"
+            seconds = true
+            "#,
+        );
+
+        assert_golden(
+            "clock_seconds",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T13:36:08Z")),
+                &Daylight::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn twelve_hours_without_blinking() {
+        let mut scene = clock(
+            r#"
+            time_zone = "Europe/London"
+            twelve_hour = true
+            blink = false
+            "#,
+        );
+
+        assert_golden(
+            "clock_twelve_hour",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T13:36:01Z")),
+                &Daylight::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn world_clock_cycles_through_our_cities_and_dates() {
+        let scene = clock(
+            r#"
+            date = true
+            cycle_seconds = 5
+            cities = [
+                { name = "NY", time_zone = "America/New_York" },
+                { name = "LA", time_zone = "America/Los_Angeles" },
+            ]
+            "#,
+        );
+        let shown = |time: &str| {
+            let (city, page) = scene.page(at(time));
+            (city.map(|city| city.name.as_str()), page)
+        };
+
+        assert_eq!(shown("2024-06-01T13:36:00Z"), (Some("NY"), Page::Time));
+        assert_eq!(shown("2024-06-01T13:36:05Z"), (Some("NY"), Page::Date));
+        assert_eq!(shown("2024-06-01T13:36:10Z"), (Some("LA"), Page::Time));
+        assert_eq!(shown("2024-06-01T13:36:19Z"), (Some("LA"), Page::Date));
+        assert_eq!(shown("2024-06-01T13:36:20Z"), (Some("NY"), Page::Time));
+
+        let mut scene = scene;
+        assert_golden(
+            "clock_world",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T13:36:10Z")),
+                &Daylight::default(),
+            ),
+        );
+        assert_golden(
+            "clock_date",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T13:36:15Z")),
+                &Daylight::default(),
+            ),
+        );
+    }
+
+    #[test]
+    fn cities_need_a_real_time_zone() {
+        let config = r#"cities = [{ name = "MOON", time_zone = "Moon/Tranquility" }]"#;
+        assert!(toml::from_str::<ClockConfig>(config).is_err());
+    }
+}
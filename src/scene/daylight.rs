@@ -0,0 +1,126 @@
+use crate::dot_matrix::DotMatrix;
+use crate::grayscale::Grayscale;
+use crate::layout::Layout;
+use crate::scene::{show_colon, Context, Scene};
+use crate::seven_segment::SevenSegment;
+
+use embedded_graphics::draw_target::{DrawTarget, DrawTargetExt};
+use embedded_graphics::geometry::Point;
+use embedded_graphics::pixelcolor::{BinaryColor, Gray2, Gray4, GrayColor};
+use embedded_graphics::primitives::{Circle, PrimitiveStyle, StyledDrawable};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref LAYOUT: Layout = Layout::from_toml(include_str!("../../layouts/daylight.toml"))
+        .expect("Our daylight layout parses");
+}
+
+/// Counts down to the next sunrise or sunset
+#[derive(Default)]
+pub struct DaylightScene;
+
+impl Scene for DaylightScene {
+    fn name(&self) -> &str {
+        "daylight"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        draw_daylight(context, matrix)
+    }
+
+    fn draw_gray(
+        &mut self,
+        context: &Context,
+        _matrix: &mut DotMatrix,
+        grayscale: &mut Grayscale,
+    ) -> anyhow::Result<()> {
+        match grayscale {
+            Grayscale::Gray2(gray) => draw_glowing_daylight(context, Gray2::new(1), gray),
+            Grayscale::Gray4(gray) => draw_glowing_daylight(context, Gray4::new(4), gray),
+        }
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let (h, m) = context.daylight.until(context.now);
+        segments.write_clock(h, m, show_colon(context));
+
+        Ok(())
+    }
+}
+
+fn draw_glowing_daylight<D, C>(context: &Context, glow: C, target: &mut D) -> anyhow::Result<()>
+where
+    C: GrayColor + From<BinaryColor>,
+    D: DrawTarget<Color = C, Error = anyhow::Error>,
+{
+    Circle::with_center(Point::new(5, 7), 9)
+        .draw_styled(&PrimitiveStyle::with_fill(glow), target)?;
+
+    draw_daylight(context, &mut target.color_converted())
+}
+
+fn draw_daylight<D>(context: &Context, matrix: &mut D) -> anyhow::Result<()>
+where
+    D: DrawTarget<Color = BinaryColor, Error = anyhow::Error>,
+{
+    LAYOUT.draw(context, matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::sunrise_sunset_api::DaylightResponse;
+    use crate::testing::{assert_golden, at, render_scene};
+
+    fn response() -> DaylightResponse {
+        DaylightResponse {
+            sunrise: at("2024-06-01T09:25:00Z"),
+            sunset: at("2024-06-02T00:24:00Z"),
+            civil_twilight_begin: at("2024-06-01T08:52:00Z"),
+            civil_twilight_end: at("2024-06-02T00:57:00Z"),
+            day_length: 53940,
+        }
+    }
+
+    #[test]
+    fn sunrise() {
+        let daylight = Daylight::Sunrise {
+            response: response(),
+        };
+        let clock = Clock::Fixed(at("2024-06-01T05:13:00Z"));
+
+        assert_golden(
+            "daylight_sunrise",
+            &render_scene(&mut DaylightScene, clock, &daylight),
+        );
+    }
+
+    #[test]
+    fn sunset_without_colon() {
+        let daylight = Daylight::Sunset {
+            response: response(),
+        };
+        let clock = Clock::Fixed(at("2024-06-01T13:36:01Z"));
+
+        assert_golden(
+            "daylight_sunset_blink",
+            &render_scene(&mut DaylightScene, clock, &daylight),
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        let clock = Clock::Fixed(at("2024-06-01T13:36:00Z"));
+
+        assert_golden(
+            "daylight_unknown",
+            &render_scene(&mut DaylightScene, clock, &Daylight::unknown(None)),
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use crate::dot_matrix::DotMatrix;
+use crate::scene::{show_colon, Context, Scene};
+use crate::seven_segment::SevenSegment;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::pixelcolor::BinaryColor;
+
+/// Every LED on, then off, each second, to get someone's attention
+#[derive(Default)]
+pub struct FlashScene;
+
+impl Scene for FlashScene {
+    fn name(&self) -> &str {
+        "flash"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        if show_colon(context) {
+            DrawTarget::clear(matrix, BinaryColor::On)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        if show_colon(context) {
+            segments.write_str(&"8.".repeat(segments.len()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::dot_matrix::DotMatrix;
+use crate::layout::Layout;
+use crate::scene::{Context, Scene};
+
+use std::path::Path;
+
+/// Whatever a layout file describes, so a screen can be rearranged without recompiling
+pub struct LayoutScene {
+    name: String,
+    layout: Layout,
+}
+
+impl LayoutScene {
+    pub fn open(path: &Path) -> anyhow::Result<LayoutScene> {
+        Ok(LayoutScene {
+            name: path.file_stem().map_or("layout".to_string(), |stem| {
+                stem.to_string_lossy().into_owned()
+            }),
+            layout: Layout::load(path)?,
+        })
+    }
+}
+
+impl Scene for LayoutScene {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        self.layout.draw(context, matrix)
+    }
+}
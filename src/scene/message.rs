@@ -0,0 +1,155 @@
+use crate::canvas::{Canvas, Viewport};
+use crate::dot_matrix::{DotMatrix, ROW_LENGTH};
+use crate::scene::{Context, Scene};
+use crate::seven_segment::SevenSegment;
+
+use chrono::{DateTime, Utc};
+use embedded_graphics::geometry::Point;
+use embedded_graphics::mono_font::ascii::FONT_5X7;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+
+const CHARACTER_WIDTH: usize =
+    (FONT_5X7.character_size.width + FONT_5X7.character_spacing) as usize;
+
+/// How fast text too long for our dot matrix scrolls by, a character a second
+const SCROLL_PIXELS_PER_SECOND: i64 = CHARACTER_WIDTH as i64;
+
+/// Some text, scrolling a character a second if it doesn't fit
+pub struct MessageScene {
+    text: String,
+    /// All of our text, which our viewport scrolls across
+    canvas: Canvas,
+    viewport: Viewport,
+    /// When we were first drawn, which we scroll from
+    started: Option<DateTime<Utc>>,
+    /// Whether we scroll at all, or just hold our start
+    scrolls: bool,
+    /// Whether we've had to scroll, because our text didn't fit
+    scrolled: bool,
+    /// How many times we've scrolled through all of our text
+    loops: usize,
+}
+
+impl MessageScene {
+    pub fn new(text: String) -> anyhow::Result<MessageScene> {
+        let width = (text.chars().count() * CHARACTER_WIDTH).max(ROW_LENGTH);
+        let mut canvas = Canvas::new(width, FONT_5X7.character_size.height as usize);
+        let style = MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
+        Text::with_baseline(&text, Point::zero(), style, Baseline::Top).draw(&mut canvas)?;
+
+        Ok(MessageScene {
+            text,
+            viewport: canvas.viewport(),
+            canvas,
+            started: None,
+            scrolls: true,
+            scrolled: false,
+            loops: 0,
+        })
+    }
+
+    /// Some text that holds still, cutting off whatever doesn't fit
+    pub fn still(text: String) -> anyhow::Result<MessageScene> {
+        Ok(MessageScene {
+            scrolls: false,
+            ..MessageScene::new(text)?
+        })
+    }
+
+    /// How many times we've scrolled through all of our text, or `None` when it fits (or we hold
+    /// still) so we never have to
+    pub fn loops(&self) -> Option<usize> {
+        self.scrolled.then_some(self.loops)
+    }
+
+    /// How long since we were first drawn at `now`
+    fn elapsed(&mut self, now: DateTime<Utc>) -> chrono::Duration {
+        let started = *self.started.get_or_insert(now);
+        (now - started).max(chrono::Duration::zero())
+    }
+
+    /// The characters we show at `now` on a 7-segment display, leaving some room between our end
+    /// and our next loop
+    fn scroll_characters(&mut self, fits: usize, now: DateTime<Utc>) -> String {
+        let length = self.text.chars().count();
+        if length <= fits || !self.scrolls {
+            return self.text.clone();
+        }
+
+        let padded: Vec<char> = self.text.chars().chain("   ".chars()).collect();
+        let scrolled = self.elapsed(now).num_seconds() as usize;
+        self.scrolled = true;
+        self.loops = scrolled / padded.len();
+
+        let offset = scrolled % padded.len();
+        padded.iter().cycle().skip(offset).take(fits).collect()
+    }
+}
+
+impl Scene for MessageScene {
+    fn name(&self) -> &str {
+        "message"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        if self.scrolls && self.viewport.max_position().x > 0 {
+            // we hold our end for a character before we start over
+            let period = self.viewport.max_position().x as i64 + CHARACTER_WIDTH as i64;
+            let scrolled =
+                self.elapsed(context.now).num_milliseconds() * SCROLL_PIXELS_PER_SECOND / 1000;
+            self.viewport
+                .scroll_to(Point::new((scrolled % period) as i32, 0));
+            self.scrolled = true;
+            self.loops = (scrolled / period) as usize;
+        }
+
+        self.canvas.show(&self.viewport, matrix);
+
+        Ok(())
+    }
+
+    fn is_scrolling(&self, _now: DateTime<Utc>) -> bool {
+        self.scrolls && self.viewport.max_position().x > 0
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let text = self.scroll_characters(segments.len(), context.now);
+        segments.write_str(&text);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::testing::{assert_golden, at, render_scene};
+
+    #[test]
+    fn scrolls_through_our_canvas() {
+        let mut scene = MessageScene::new("Hello World".to_string()).unwrap();
+        let start = at("2024-06-01T12:00:00Z");
+        let mut render = |millis: i64| {
+            let clock = Clock::Fixed(start + chrono::Duration::milliseconds(millis));
+            render_scene(&mut scene, clock, &Daylight::default())
+        };
+
+        assert_golden("message_start", &render(0));
+        // 11 characters are 23 pixels past our edge, which takes 4.6s
+        assert_ne!(render(4_400), render(4_600));
+        assert_golden("message_end", &render(4_600));
+        // and we hold our end for a character before we start over
+        assert_golden("message_end", &render(5_500));
+        assert_golden("message_start", &render(5_600));
+        assert_eq!(scene.loops(), Some(1));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::dot_matrix::DotMatrix;
+use crate::notification::{NotificationConfig, NotificationStyle};
+use crate::scene::{show_colon, Context, MessageScene, Scene};
+use crate::seven_segment::SevenSegment;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// How long we show text that doesn't scroll each time it repeats
+const STILL_DURATION: Duration = Duration::seconds(5);
+
+/// A notification's text, scrolling, blinking or holding still
+pub struct NotificationScene {
+    style: NotificationStyle,
+    message: MessageScene,
+    /// How many times we show our text
+    repeat: u32,
+    /// When we were first drawn
+    shown: Option<DateTime<Utc>>,
+}
+
+impl NotificationScene {
+    pub fn new(notification: &NotificationConfig) -> anyhow::Result<NotificationScene> {
+        let text = notification.text.clone();
+        let message = match notification.style {
+            NotificationStyle::Scroll => MessageScene::new(text)?,
+            NotificationStyle::Flash | NotificationStyle::Static => MessageScene::still(text)?,
+        };
+
+        Ok(NotificationScene {
+            style: notification.style,
+            message,
+            repeat: notification.repeat.max(1),
+            shown: None,
+        })
+    }
+
+    /// Whether we've shown our text as many times as we repeat, counting each time we scroll
+    /// through it, or a few seconds each time for text that holds still
+    pub fn is_done(&self, now: DateTime<Utc>) -> bool {
+        let shown = match (self.message.loops(), self.shown) {
+            (Some(loops), _) => loops,
+            (None, Some(shown)) => {
+                ((now - shown).num_seconds().max(0) / STILL_DURATION.num_seconds()) as usize
+            }
+            (None, None) => 0,
+        };
+
+        shown >= self.repeat as usize
+    }
+
+    /// Flashing notifications are dark every other second
+    fn is_dark(&self, context: &Context) -> bool {
+        self.style == NotificationStyle::Flash && !show_colon(context)
+    }
+}
+
+impl Scene for NotificationScene {
+    fn name(&self) -> &str {
+        "notification"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        self.shown.get_or_insert(context.now);
+        if self.is_dark(context) {
+            return Ok(());
+        }
+
+        self.message.draw(context, matrix)
+    }
+
+    fn is_scrolling(&self, now: DateTime<Utc>) -> bool {
+        self.message.is_scrolling(now)
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        self.shown.get_or_insert(context.now);
+        if self.is_dark(context) {
+            return Ok(());
+        }
+
+        self.message.draw_segments(context, segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::testing::{assert_golden, at, render_scene};
+
+    #[test]
+    fn flashes_its_text() {
+        let notification: NotificationConfig =
+            serde_json::from_str(r#"{ "text": "Door open!", "style": "flash" }"#).unwrap();
+        let mut scene = NotificationScene::new(&notification).unwrap();
+        let daylight = Daylight::default();
+
+        assert_golden(
+            "notification_flash",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T12:00:00Z")),
+                &daylight,
+            ),
+        );
+        assert_golden(
+            "notification_dark",
+            &render_scene(
+                &mut scene,
+                Clock::Fixed(at("2024-06-01T12:00:01Z")),
+                &daylight,
+            ),
+        );
+    }
+
+    #[test]
+    fn repeats_by_scrolling_through_its_text() {
+        let notification: NotificationConfig =
+            serde_json::from_str(r#"{ "text": "Washer's done", "repeat": 2 }"#).unwrap();
+        let mut scene = NotificationScene::new(&notification).unwrap();
+        let (start, daylight) = (at("2024-06-01T12:00:00Z"), Daylight::default());
+
+        let mut now = start;
+        loop {
+            render_scene(&mut scene, Clock::Fixed(now), &daylight);
+            if scene.is_done(now) {
+                break;
+            }
+            now += Duration::milliseconds(100);
+            assert!(now - start < Duration::minutes(1), "We never finished");
+        }
+        // 13 characters scroll 33 pixels then hold for 5 each time, however often we're drawn
+        assert_eq!(now - start, Duration::milliseconds(2 * 7_600));
+
+        let clock = Clock::Fixed(start);
+
+        let still: NotificationConfig =
+            serde_json::from_str(r#"{ "text": "Washer's done", "style": "static" }"#).unwrap();
+        let mut still = NotificationScene::new(&still).unwrap();
+        render_scene(&mut still, clock, &daylight);
+        assert!(!still.is_done(at("2024-06-01T12:00:04Z")));
+        assert!(still.is_done(at("2024-06-01T12:00:05Z")));
+    }
+}
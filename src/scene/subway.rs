@@ -0,0 +1,302 @@
+use crate::dot_matrix::{DotMatrix, ROW_LENGTH};
+use crate::font::{FontStyle, NARROW_4X7, TINY_3X5};
+use crate::gtfs_realtime::{query_feed, Arrival};
+use crate::poller::{Polled, Poller};
+use crate::scene::{Context, Scene};
+use crate::seven_segment::SevenSegment;
+use crate::sprite::Sprite;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, StyledDrawable};
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Our route bullets are a letter (or digit) knocked out of a rounded box
+const BULLET: Size = Size::new(5, 7);
+
+/// Our `{ type = "subway" }` scenes
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SubwayConfig {
+    /// A GTFS-realtime feed's URL, or a file with one in it
+    pub feed: String,
+    /// Our station's stop id from the GTFS `stops.txt`, like `127` for Times Sq-42 St
+    pub station: String,
+    pub direction: Direction,
+    /// Only show these routes, every route stopping at our station by default
+    #[serde(default)]
+    pub routes: Vec<String>,
+    /// How many trains we show, if they fit
+    #[serde(default = "default_trains")]
+    pub trains: usize,
+    /// How often we fetch our feed, the MTA's update about every 30 seconds
+    #[serde(default = "default_refresh_seconds")]
+    pub refresh_seconds: u64,
+}
+
+fn default_trains() -> usize {
+    3
+}
+
+fn default_refresh_seconds() -> u64 {
+    30
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    North,
+    South,
+}
+
+impl SubwayConfig {
+    /// The stop id of our station's platform heading in our direction
+    fn stop_id(&self) -> String {
+        let suffix = match self.direction {
+            Direction::North => 'N',
+            Direction::South => 'S',
+        };
+
+        format!("{}{suffix}", self.station)
+    }
+}
+
+/// The next few trains at a station, each with its route bullet and how many minutes away it is
+pub struct SubwayScene {
+    config: SubwayConfig,
+    /// Our station's arrivals, trains we already know about age out as they arrive
+    feed: Poller<Vec<Arrival>>,
+}
+
+impl SubwayScene {
+    /// Start polling our feed in the background
+    pub fn open(config: SubwayConfig) -> anyhow::Result<SubwayScene> {
+        if config.refresh_seconds == 0 {
+            return Err(anyhow!(
+                "Our subway's refresh_seconds needs to be at least 1"
+            ));
+        }
+
+        let (source, stop_id) = (config.feed.clone(), config.stop_id());
+        let feed = Poller::spawn(
+            format!("{stop_id} arrivals"),
+            Duration::from_secs(config.refresh_seconds),
+            move || fetch(source.clone(), stop_id.clone()),
+        );
+
+        Ok(SubwayScene { config, feed })
+    }
+
+    /// Trains that haven't arrived yet on our routes, soonest first, and whether we might be
+    /// missing some
+    fn upcoming(&self, now: DateTime<Utc>) -> (Vec<Arrival>, bool) {
+        let feed = self.feed.lock();
+        let arrivals = feed
+            .value
+            .iter()
+            .filter(|arrival| arrival.time >= now)
+            .filter(|arrival| {
+                self.config.routes.is_empty() || self.config.routes.contains(&arrival.route)
+            })
+            .take(self.config.trains)
+            .cloned()
+            .collect();
+
+        (arrivals, feed.failing)
+    }
+}
+
+async fn fetch(source: String, stop_id: String) -> anyhow::Result<Polled<Vec<Arrival>>> {
+    let message = query_feed(&source).await?;
+
+    Ok(Polled {
+        value: message.arrivals(&stop_id),
+        failing: false,
+    })
+}
+
+/// What goes in our route's bullet, shuttles are all an `S`
+fn bullet(route: &str) -> char {
+    match route {
+        "GS" | "FS" | "H" | "SI" => 'S',
+        // express trains like `6X` share their local's bullet
+        route => route.chars().next().unwrap_or('?'),
+    }
+}
+
+/// Whole minutes until our train gets here
+fn minutes(arrival: &Arrival, now: DateTime<Utc>) -> String {
+    ((arrival.time - now).num_minutes()).to_string()
+}
+
+fn draw_bullet(route: &str, top_left: Point, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+    Rectangle::new(top_left, BULLET)
+        .draw_styled(&PrimitiveStyle::with_fill(BinaryColor::On), matrix)?;
+    // round our corners
+    let right = BULLET.width as i32 - 1;
+    let bottom = BULLET.height as i32 - 1;
+    for corner in [(0, 0), (right, 0), (0, bottom), (right, bottom)] {
+        Rectangle::new(top_left + Point::from(corner), Size::new(1, 1))
+            .draw_styled(&PrimitiveStyle::with_fill(BinaryColor::Off), matrix)?;
+    }
+
+    let style = FontStyle::new(&TINY_3X5, BinaryColor::Off);
+    let letter = bullet(route).to_string();
+    Text::with_baseline(&letter, top_left + Point::new(1, 1), style, Baseline::Top).draw(matrix)?;
+
+    Ok(())
+}
+
+impl Scene for SubwayScene {
+    fn name(&self) -> &str {
+        "subway"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        let (arrivals, failing) = self.upcoming(context.now);
+        if arrivals.is_empty() {
+            if failing {
+                Sprite::builtin("wifi-error")?.draw(context.now, Point::zero(), matrix)?;
+            }
+            let style = FontStyle::new(&NARROW_4X7, BinaryColor::On);
+            let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width("--") as i32;
+            Text::with_baseline("--", Point::new(left, 0), style, Baseline::Top).draw(matrix)?;
+
+            return Ok(());
+        }
+
+        // our minutes sit on the bottom of our bullets
+        let minutes_top = (BULLET.height - TINY_3X5.height()) as i32;
+        let style = FontStyle::new(&TINY_3X5, BinaryColor::On);
+        let mut left = 0;
+        for arrival in &arrivals {
+            let minutes = minutes(arrival, context.now);
+            let width = (BULLET.width + 1 + TINY_3X5.tight_width(&minutes)) as i32;
+            // only show the trains that fit
+            if left + width > ROW_LENGTH as i32 {
+                break;
+            }
+
+            draw_bullet(&arrival.route, Point::new(left, 0), matrix)?;
+            let minutes_at = Point::new(left + BULLET.width as i32 + 1, minutes_top);
+            Text::with_baseline(&minutes, minutes_at, style, Baseline::Top).draw(matrix)?;
+
+            left += width + 2;
+        }
+
+        Ok(())
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let (arrivals, _) = self.upcoming(context.now);
+        let text = arrivals
+            .iter()
+            .map(|arrival| {
+                format!(
+                    "{}{}",
+                    bullet(&arrival.route),
+                    minutes(arrival, context.now)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        segments.write_str(if text.is_empty() { "--" } else { &text });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Clock;
+    use crate::daylight::Daylight;
+    use crate::testing::{assert_golden, at, gtfs_feed, render_scene, wait_until};
+    use prost::Message;
+    use std::env;
+    use std::fs;
+
+    fn config(feed: &str) -> SubwayConfig {
+        toml::from_str(&format!(
+            r#"
+            feed = "{feed}"
+            station = "127"
+            direction = "north"
+            "#
+        ))
+        .unwrap()
+    }
+
+    /// A scene that already knows about `trains`, without polling anything
+    fn scene(config: SubwayConfig, now: DateTime<Utc>, trains: &[(&str, i64)]) -> SubwayScene {
+        let arrivals = gtfs_feed(&config.stop_id(), now, trains).arrivals(&config.stop_id());
+
+        SubwayScene {
+            config,
+            feed: Poller::fixed(arrivals),
+        }
+    }
+
+    #[test]
+    fn next_three_trains() {
+        let now = at("2024-06-01T13:36:00Z");
+        // the first train has already left
+        let trains = [("1", -30), ("2", 90), ("GS", 250), ("3", 530), ("1", 600)];
+        let mut scene = scene(config("feed.pb"), now, &trains);
+
+        assert_golden(
+            "subway_three_trains",
+            &render_scene(&mut scene, Clock::Fixed(now), &Daylight::default()),
+        );
+    }
+
+    #[test]
+    fn only_the_trains_that_fit() {
+        let now = at("2024-06-01T13:36:00Z");
+        let trains = [("6X", 720), ("6", 900), ("6", 1200)];
+        let mut config = config("feed.pb");
+        config.routes = vec!["6X".to_string(), "6".to_string()];
+        let mut scene = scene(config, now, &trains);
+
+        assert_golden(
+            "subway_two_trains",
+            &render_scene(&mut scene, Clock::Fixed(now), &Daylight::default()),
+        );
+    }
+
+    #[test]
+    fn filters_our_routes() {
+        let now = at("2024-06-01T13:36:00Z");
+        let mut config = config("feed.pb");
+        config.routes = vec!["2".to_string()];
+        let scene = scene(config, now, &[("1", 60), ("2", 120), ("3", 180)]);
+
+        let (arrivals, _) = scene.upcoming(now);
+        assert_eq!(arrivals.len(), 1);
+        assert_eq!(arrivals[0].route, "2");
+    }
+
+    #[tokio::test]
+    async fn polls_our_feed_from_a_file() {
+        let now = Utc::now();
+        let path = env::temp_dir().join(format!("subway-{}.pb", std::process::id()));
+        fs::write(&path, gtfs_feed("127N", now, &[("1", 300)]).encode_to_vec()).unwrap();
+
+        let scene = SubwayScene::open(config(path.to_str().unwrap())).unwrap();
+        wait_until(|| !scene.upcoming(now).0.is_empty()).await;
+        fs::remove_file(&path).unwrap();
+
+        let (arrivals, failing) = scene.upcoming(now);
+        assert_eq!(arrivals.len(), 1);
+        assert!(!failing);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::dot_matrix::{DotMatrix, DISPLAY_HEIGHT, ROW_LENGTH};
+use crate::font::{FontStyle, NARROW_4X7};
+use crate::layout::{countdown_width, draw_countdown};
+use crate::scene::{show_colon, Context, Scene};
+use crate::seven_segment::SevenSegment;
+use crate::timer::{Remaining, Timer};
+
+use chrono::Duration;
+use embedded_graphics::geometry::Point;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+
+/// Our `{ type = "timer" }` scenes, our timers themselves come from the `[[timer]]` tables in our
+/// config or our remote control
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TimerSceneConfig {
+    /// The timer we show, whichever's done soonest by default
+    pub name: Option<String>,
+}
+
+/// Counts down to one of our timers, with its icon
+pub struct TimerScene {
+    config: TimerSceneConfig,
+}
+
+impl TimerScene {
+    pub fn new(config: TimerSceneConfig) -> TimerScene {
+        TimerScene { config }
+    }
+
+    fn timer<'a>(&self, context: &Context<'a>) -> Option<&'a Timer> {
+        match &self.config.name {
+            Some(name) => context.timers.get(name),
+            None => context.timers.next(),
+        }
+    }
+}
+
+impl Scene for TimerScene {
+    fn name(&self) -> &str {
+        "timer"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        let style = FontStyle::new(&NARROW_4X7, BinaryColor::On);
+        let Some(timer) = self.timer(context) else {
+            let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width("--") as i32;
+            Text::with_baseline("--", Point::new(left, 0), style, Baseline::Top).draw(matrix)?;
+
+            return Ok(());
+        };
+
+        // right aligned, with our text's left edge
+        let left = match timer.remaining(context.now) {
+            Remaining::Days(days) => {
+                let text = format!("{days}D");
+                let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width(&text) as i32;
+                Text::with_baseline(&text, Point::new(left, 0), style, Baseline::Top)
+                    .draw(matrix)?;
+                left
+            }
+            Remaining::HoursMinutes(h, m) => {
+                let left = ROW_LENGTH as i32 - countdown_width(&NARROW_4X7, (h, m)) as i32;
+                let colon = show_colon(context);
+                draw_countdown(&NARROW_4X7, (h, m), colon, Point::new(left, 0), matrix)?;
+                left
+            }
+            // our seconds already tick, so our colon doesn't blink
+            Remaining::MinutesSeconds(m, s) => {
+                let left = ROW_LENGTH as i32 - countdown_width(&NARROW_4X7, (m, s)) as i32;
+                draw_countdown(&NARROW_4X7, (m, s), true, Point::new(left, 0), matrix)?;
+                left
+            }
+        };
+
+        if let Some(icon) = &timer.icon {
+            let size = icon.size();
+            if (size.width as i32) < left {
+                let top = (DISPLAY_HEIGHT as i32 - size.height as i32) / 2;
+                icon.draw(context.now, Point::new(0, top), matrix)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        match self.timer(context) {
+            None => segments.write_str("--"),
+            Some(timer) => match timer.remaining(context.now) {
+                Remaining::Days(days) => segments.write_str_right(&format!("{days}d")),
+                _ => segments.write_countdown((timer.target - context.now).max(Duration::zero())),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneConfig;
+    use crate::snapshot::Snapshot;
+    use crate::testing::{assert_golden, at, TestContext};
+    use crate::timer::{TimerConfig, Timers};
+    use chrono::{DateTime, Utc};
+
+    fn timers(now: DateTime<Utc>) -> Timers {
+        #[derive(Deserialize)]
+        struct Config {
+            timer: Vec<TimerConfig>,
+        }
+        let config: Config = toml::from_str(
+            r#"
+            [[timer]]
+            name = "vacation"
+            at = "2024-06-13T22:00:00Z"
+
+            [[timer]]
+            name = "launch"
+            at = "2024-06-01T15:30:00Z"
+            format = "hours-minutes"
+            icon = "up-arrow"
+
+            [[timer]]
+            name = "pomodoro"
+            minutes = 25
+            icon = "hourglass"
+            "#,
+        )
+        .unwrap();
+
+        Timers::new(&config.timer, now).unwrap()
+    }
+
+    fn render(scene: &str, context: &TestContext) -> Snapshot {
+        let mut scene = match toml::from_str(&format!("type = \"timer\"\n{scene}")).unwrap() {
+            SceneConfig::Timer(config) => TimerScene::new(config),
+            config => panic!("Not a timer: {config:?}"),
+        };
+
+        context.render(&mut scene)
+    }
+
+    #[test]
+    fn counts_down_in_our_format() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut context = TestContext::new(now).timers(timers(now));
+
+        assert_golden("timer_days", &render("name = \"vacation\"", &context));
+        // our next timer, by default
+        assert_golden("timer_hours_minutes", &render("", &context));
+
+        context.timers.start("pomodoro", now).unwrap();
+        context.now = now + Duration::seconds(7 * 60 + 3);
+        assert_golden("timer_pomodoro", &render("name = \"pomodoro\"", &context));
+    }
+
+    #[test]
+    fn waits_for_our_timer_to_start() {
+        let now = at("2024-06-01T12:00:00Z");
+        let context = TestContext::new(now).timers(timers(now));
+        assert_golden(
+            "timer_not_started",
+            &render("name = \"pomodoro\"", &context),
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::dot_matrix::{DotMatrix, DISPLAY_HEIGHT, ROW_LENGTH};
+use crate::font::{FontStyle, NARROW_4X7, TINY_3X5};
+use crate::scene::{Context, Scene};
+use crate::seven_segment::SevenSegment;
+use crate::sprite::Sprite;
+
+use anyhow::anyhow;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, StyledDrawable};
+use embedded_graphics::text::{Baseline, Text};
+use embedded_graphics::Drawable;
+use serde::Deserialize;
+
+/// Our `{ type = "weather" }` scenes, our forecast itself comes from the `[weather]` section of
+/// our config
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherSceneConfig {
+    /// How long we show our current temperature (or today's high and low) before switching
+    #[serde(default = "default_cycle_seconds")]
+    pub cycle_seconds: u32,
+}
+
+fn default_cycle_seconds() -> u32 {
+    5
+}
+
+/// What we're showing right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Current,
+    HighLow,
+}
+
+/// An icon for the current conditions, alternating between our temperature and today's high and
+/// low
+pub struct WeatherScene {
+    config: WeatherSceneConfig,
+}
+
+impl WeatherScene {
+    pub fn new(config: WeatherSceneConfig) -> anyhow::Result<WeatherScene> {
+        if config.cycle_seconds == 0 {
+            return Err(anyhow!(
+                "Our weather's cycle_seconds needs to be at least 1"
+            ));
+        }
+
+        Ok(WeatherScene { config })
+    }
+
+    /// Loops from the unix epoch like our clock does
+    fn page(&self, context: &Context) -> Page {
+        let cycle = context
+            .now
+            .timestamp()
+            .div_euclid(self.config.cycle_seconds as i64);
+        match context.weather.high_low() {
+            Some(_) if cycle.rem_euclid(2) == 1 => Page::HighLow,
+            _ => Page::Current,
+        }
+    }
+}
+
+/// Our temperatures as whole degrees
+fn degrees(temperature: f64) -> String {
+    (temperature.round() as i64).to_string()
+}
+
+impl Scene for WeatherScene {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn draw(&mut self, context: &Context, matrix: &mut DotMatrix) -> anyhow::Result<()> {
+        // right aligned, with our text's left edge
+        let left = match (self.page(context), context.weather.high_low()) {
+            (Page::HighLow, Some((high, low))) => {
+                let text = format!("{}/{}", degrees(high), degrees(low));
+                let left = ROW_LENGTH as i32 - TINY_3X5.tight_width(&text) as i32;
+                // on the same baseline as our current temperature
+                let top = (NARROW_4X7.height() - TINY_3X5.height()) as i32;
+                let style = FontStyle::new(&TINY_3X5, BinaryColor::On);
+                Text::with_baseline(&text, Point::new(left, top), style, Baseline::Top)
+                    .draw(matrix)?;
+
+                left
+            }
+            _ => {
+                let text = context
+                    .weather
+                    .current()
+                    .map_or("--".to_string(), |current| degrees(current.temperature_2m));
+                // our degree sign is a 3x3 ring after a space
+                let left = ROW_LENGTH as i32 - NARROW_4X7.tight_width(&text) as i32 - 4;
+                let style = FontStyle::new(&NARROW_4X7, BinaryColor::On);
+                Text::with_baseline(&text, Point::new(left, 0), style, Baseline::Top)
+                    .draw(matrix)?;
+                Rectangle::new(Point::new(ROW_LENGTH as i32 - 3, 0), Size::new(3, 3))
+                    .draw_styled(&PrimitiveStyle::with_stroke(BinaryColor::On, 1), matrix)?;
+
+                left
+            }
+        };
+
+        // leave a column between our icon and our text
+        let icon = Sprite::builtin(context.weather.icon())?;
+        let size = icon.size();
+        if (size.width as i32) < left {
+            let top = (DISPLAY_HEIGHT as i32 - size.height as i32) / 2;
+            icon.draw(context.now, Point::new(0, top), matrix)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_segments(
+        &mut self,
+        context: &Context,
+        segments: &mut SevenSegment,
+    ) -> anyhow::Result<()> {
+        let text = match (self.page(context), context.weather.high_low()) {
+            (Page::HighLow, Some((high, low))) => format!("{} {}", degrees(high), degrees(low)),
+            _ => context
+                .weather
+                .current()
+                .map_or("--".to_string(), |current| degrees(current.temperature_2m)),
+        };
+        segments.write_str_right(&text);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_meteo_api::{CurrentWeather, DailyWeather, WeatherResponse};
+    use crate::snapshot::Snapshot;
+    use crate::testing::{assert_golden, at, TestContext};
+    use crate::weather::Weather;
+    use chrono::{DateTime, Utc};
+
+    fn weather(temperature: f64, weather_code: u8) -> Weather {
+        Weather::Known(WeatherResponse {
+            current: CurrentWeather {
+                temperature_2m: temperature,
+                weather_code,
+                is_day: 1,
+            },
+            daily: DailyWeather {
+                temperature_2m_max: vec![75.2],
+                temperature_2m_min: vec![-4.6],
+            },
+        })
+    }
+
+    fn render(weather: Weather, now: DateTime<Utc>) -> Snapshot {
+        let mut scene = WeatherScene::new(WeatherSceneConfig { cycle_seconds: 5 }).unwrap();
+
+        TestContext::new(now).weather(weather).render(&mut scene)
+    }
+
+    #[test]
+    fn current_temperature() {
+        assert_golden(
+            "weather_current",
+            &render(weather(71.6, 3), at("2024-06-01T13:36:00Z")),
+        );
+    }
+
+    #[test]
+    fn high_and_low() {
+        assert_golden(
+            "weather_high_low",
+            &render(weather(71.6, 3), at("2024-06-01T13:36:05Z")),
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert_golden(
+            "weather_unknown",
+            &render(Weather::Unknown, at("2024-06-01T13:36:05Z")),
+        );
+    }
+}
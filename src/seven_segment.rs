@@ -0,0 +1,424 @@
+use crate::brightness::Brightness;
+use crate::max7219::{DecodeMode, Intensity, Max7219, DIGITS};
+
+use chrono::Duration;
+
+/// Digits driven by a single MAX7219
+pub const DIGITS_PER_SEGMENT: usize = DIGITS.len();
+
+/// Decimal point, shared by both Code-B and raw segment data
+pub const DECIMAL_POINT: u8 = 0b10000000;
+
+/// How a 7-segment chain interprets the digit data we send it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentMode {
+    /// Let the MAX7219 decode `0-9 - E H L P` for us
+    CodeB,
+    /// Send the segments ourselves, which lets us show most of ASCII
+    Raw,
+}
+
+/// Display Positions
+/// 0 1 2 3 4 5 6 7 | 8 9 ...
+/// Position 0 is the left most digit of the first segment in the chain
+pub struct SevenSegment {
+    pub max: Max7219,
+    mode: SegmentMode,
+    /// One byte per digit, in display order
+    digits: Vec<u8>,
+    intensity: Intensity,
+}
+
+impl SevenSegment {
+    pub fn new(
+        mut max: Max7219,
+        mode: SegmentMode,
+        intensity: Intensity,
+    ) -> anyhow::Result<SevenSegment> {
+        let chained_segments = max.chained_segments();
+
+        // Scan Limit drives how many digits are shown, show all 8 of them
+        max.set_scan_limit(7)?;
+        max.set_decode_mode(match mode {
+            SegmentMode::CodeB => DecodeMode::DecodeAll,
+            SegmentMode::Raw => DecodeMode::NoDecode,
+        })?;
+        // turn off test_mode
+        max.set_display_test(false)?;
+
+        max.set_display_on(true)?;
+
+        let mut display = SevenSegment {
+            max,
+            mode,
+            digits: vec![0b0; chained_segments * DIGITS_PER_SEGMENT],
+            intensity: 0x0,
+        };
+        display.clear();
+        display.set_intensity(intensity)?;
+
+        Ok(display)
+    }
+
+    pub fn len(&self) -> usize {
+        self.digits.len()
+    }
+
+    pub fn set_intensity(&mut self, intensity: Intensity) -> anyhow::Result<()> {
+        self.intensity = intensity;
+        self.max.set_intensity(intensity)
+    }
+
+    pub fn set_brightness(&mut self, brightness: Brightness) -> anyhow::Result<()> {
+        match brightness {
+            Brightness::Off => self.max.set_display_on(false),
+            Brightness::On(intensity) => {
+                self.set_intensity(intensity)?;
+                self.max.set_display_on(true)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        let blank = self.encode(' ');
+        self.digits.fill(blank);
+    }
+
+    /// Set the raw digit data, which is either a Code-B character or segments depending on our mode
+    pub fn set_digit(&mut self, position: usize, data: u8) {
+        if let Some(digit) = self.digits.get_mut(position) {
+            *digit = data;
+        }
+    }
+
+    pub fn set_char(&mut self, position: usize, c: char, decimal_point: bool) {
+        let data = self.encode(c) | if decimal_point { DECIMAL_POINT } else { 0b0 };
+        self.set_digit(position, data);
+    }
+
+    /// Write our text starting at the left most digit, a `.` lights the decimal point of the
+    /// previous character. Anything that doesn't fit is dropped.
+    pub fn write_str(&mut self, text: &str) {
+        for (position, (c, decimal_point)) in cells(text).into_iter().enumerate() {
+            self.set_char(position, c, decimal_point);
+        }
+    }
+
+    /// Write our text so it ends on the right most digit
+    pub fn write_str_right(&mut self, text: &str) {
+        let cells = cells(text);
+        let start = self.len().saturating_sub(cells.len());
+        let skip = cells.len().saturating_sub(self.len());
+        for (position, (c, decimal_point)) in cells.into_iter().skip(skip).enumerate() {
+            self.set_char(start + position, c, decimal_point);
+        }
+    }
+
+    /// `HH.MM` using the decimal point as our colon
+    pub fn write_clock(&mut self, hours: usize, minutes: usize, show_colon: bool) {
+        let separator = if show_colon { "." } else { "" };
+        self.write_str_right(&format!("{hours:02}{separator}{minutes:02}"));
+    }
+
+    /// `H.MM.SS` while there are hours left, otherwise `M.SS`
+    pub fn write_countdown(&mut self, remaining: Duration) {
+        let sign = if remaining < Duration::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let seconds = remaining.num_seconds().unsigned_abs();
+        let (h, m, s) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+
+        if h > 0 {
+            self.write_str_right(&format!("{sign}{h}.{m:02}.{s:02}"));
+        } else {
+            self.write_str_right(&format!("{sign}{m}.{s:02}"));
+        }
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        let chained_segments = self.max.chained_segments();
+        let mut buffer = vec![0b0; chained_segments * 2];
+        for (digit_i, digit) in DIGITS.iter().enumerate() {
+            for segment in 0..chained_segments {
+                buffer[segment * 2] = *digit as u8;
+                buffer[segment * 2 + 1] = self.digits[position(segment, digit_i)];
+            }
+            self.max.write(&buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn encode(&self, c: char) -> u8 {
+        match self.mode {
+            SegmentMode::CodeB => code_b(c),
+            SegmentMode::Raw => segments(c),
+        }
+    }
+}
+
+impl Drop for SevenSegment {
+    fn drop(&mut self) {
+        if let Err(error) = self.max.set_display_on(false) {
+            log::error!("Failed to shutdown display: {error:?}")
+        }
+    }
+}
+
+/// Digit0 is the right most digit on our 8 digit modules
+fn position(segment: usize, digit: usize) -> usize {
+    segment * DIGITS_PER_SEGMENT + (DIGITS_PER_SEGMENT - 1 - digit)
+}
+
+/// Split our text into characters, folding each `.` into the decimal point of the character
+/// before it
+fn cells(text: &str) -> Vec<(char, bool)> {
+    let mut cells: Vec<(char, bool)> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match cells.last_mut() {
+            Some((_, decimal_point)) if c == '.' && !*decimal_point => *decimal_point = true,
+            _ if c == '.' => cells.push((' ', true)),
+            _ => cells.push((c, false)),
+        }
+    }
+
+    cells
+}
+
+/// Code-B font, anything the MAX7219 can't show is blank
+pub fn code_b(c: char) -> u8 {
+    match c.to_ascii_uppercase() {
+        '0'..='9' => c as u8 - b'0',
+        '-' => 0xA,
+        'E' => 0xB,
+        'H' => 0xC,
+        'L' => 0xD,
+        'P' => 0xE,
+        _ => 0xF,
+    }
+}
+
+/// Raw segments for a character, anything outside of printable ASCII is blank
+pub fn segments(c: char) -> u8 {
+    match c {
+        ' '..='~' => ASCII_SEGMENTS[c as usize - ' ' as usize],
+        _ => 0b0,
+    }
+}
+
+/// Segment bits for printable ASCII starting at ' '
+/// DP A B C D E F G
+const ASCII_SEGMENTS: [u8; 95] = [
+    0b00000000, // ' '
+    0b10110000, // '!'
+    0b00100010, // '"'
+    0b00111111, // '#'
+    0b01011011, // '$'
+    0b10100101, // '%'
+    0b00110001, // '&'
+    0b00000010, // '\''
+    0b01001010, // '('
+    0b01101000, // ')'
+    0b01000010, // '*'
+    0b00000111, // '+'
+    0b00000100, // ','
+    0b00000001, // '-'
+    0b10000000, // '.'
+    0b00100101, // '/'
+    0b01111110, // '0'
+    0b00110000, // '1'
+    0b01101101, // '2'
+    0b01111001, // '3'
+    0b00110011, // '4'
+    0b01011011, // '5'
+    0b01011111, // '6'
+    0b01110000, // '7'
+    0b01111111, // '8'
+    0b01111011, // '9'
+    0b01001000, // ':'
+    0b01011000, // ';'
+    0b01000011, // '<'
+    0b00001001, // '='
+    0b01100001, // '>'
+    0b11100101, // '?'
+    0b01111101, // '@'
+    0b01110111, // 'A'
+    0b00011111, // 'B'
+    0b01001110, // 'C'
+    0b00111101, // 'D'
+    0b01001111, // 'E'
+    0b01000111, // 'F'
+    0b01011110, // 'G'
+    0b00110111, // 'H'
+    0b00000110, // 'I'
+    0b00111100, // 'J'
+    0b01010111, // 'K'
+    0b00001110, // 'L'
+    0b01010100, // 'M'
+    0b01110110, // 'N'
+    0b01111110, // 'O'
+    0b01100111, // 'P'
+    0b01101011, // 'Q'
+    0b01100110, // 'R'
+    0b01011011, // 'S'
+    0b00001111, // 'T'
+    0b00111110, // 'U'
+    0b00111110, // 'V'
+    0b00101010, // 'W'
+    0b00110111, // 'X'
+    0b00111011, // 'Y'
+    0b01101101, // 'Z'
+    0b01001110, // '['
+    0b00010011, // '\\'
+    0b01111000, // ']'
+    0b01100010, // '^'
+    0b00001000, // '_'
+    0b00100000, // '`'
+    0b01111101, // 'a'
+    0b00011111, // 'b'
+    0b00001101, // 'c'
+    0b00111101, // 'd'
+    0b01101111, // 'e'
+    0b01000111, // 'f'
+    0b01111011, // 'g'
+    0b00010111, // 'h'
+    0b00000100, // 'i'
+    0b00011000, // 'j'
+    0b01010111, // 'k'
+    0b00000110, // 'l'
+    0b00010100, // 'm'
+    0b00010101, // 'n'
+    0b00011101, // 'o'
+    0b01100111, // 'p'
+    0b01110011, // 'q'
+    0b00000101, // 'r'
+    0b01011011, // 's'
+    0b00001111, // 't'
+    0b00011100, // 'u'
+    0b00011100, // 'v'
+    0b00010100, // 'w'
+    0b00110111, // 'x'
+    0b00111011, // 'y'
+    0b01101101, // 'z'
+    0b00110001, // '{'
+    0b00000110, // '|'
+    0b00000111, // '}'
+    0b01000000, // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RecordingTransport;
+
+    fn segment_display(mode: SegmentMode) -> SevenSegment {
+        let max = Max7219::new(1, Box::new(RecordingTransport::new(1)));
+        SevenSegment::new(max, mode, 0x0).unwrap()
+    }
+
+    const BLANK: u8 = 0xF;
+
+    #[test]
+    fn code_b_covers_what_the_max7219_can_show() {
+        assert_eq!(code_b('0'), 0x0);
+        assert_eq!(code_b('9'), 0x9);
+        assert_eq!(
+            ['-', 'E', 'H', 'L', 'P'].map(code_b),
+            [0xA, 0xB, 0xC, 0xD, 0xE]
+        );
+        // lowercase letters are shown as uppercase
+        assert_eq!(code_b('h'), 0xC);
+        assert_eq!(code_b('A'), BLANK);
+        assert_eq!(code_b(' '), BLANK);
+    }
+
+    #[test]
+    fn ascii_segments() {
+        assert_eq!(segments(' '), 0b0);
+        assert_eq!(segments('0'), 0b01111110);
+        assert_eq!(segments('8'), 0b01111111);
+        assert_eq!(segments('A'), 0b01110111);
+        assert_eq!(segments('-'), 0b00000001);
+        assert_eq!(segments('~'), 0b01000000);
+        // outside of printable ASCII
+        assert_eq!(segments('\n'), 0b0);
+        assert_eq!(segments('é'), 0b0);
+    }
+
+    #[test]
+    fn folds_decimal_points() {
+        let mut display = segment_display(SegmentMode::CodeB);
+        display.write_str("1.2..3");
+        assert_eq!(
+            display.digits,
+            [
+                0x1 | DECIMAL_POINT,
+                0x2 | DECIMAL_POINT,
+                BLANK | DECIMAL_POINT,
+                0x3,
+                BLANK,
+                BLANK,
+                BLANK,
+                BLANK,
+            ]
+        );
+
+        let mut display = segment_display(SegmentMode::Raw);
+        display.write_str_right("HI.");
+        assert_eq!(
+            display.digits[5..],
+            [0b0, 0b00110111, 0b00000110 | DECIMAL_POINT]
+        );
+    }
+
+    #[test]
+    fn drops_what_does_not_fit() {
+        let mut display = segment_display(SegmentMode::CodeB);
+        display.write_str("0123456789");
+        assert_eq!(display.digits, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        display.clear();
+        display.write_str_right("0123456789");
+        assert_eq!(display.digits, [2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn counts_down() {
+        let countdown = |remaining: Duration| {
+            let mut display = segment_display(SegmentMode::CodeB);
+            display.write_countdown(remaining);
+            display.digits.clone()
+        };
+
+        let dotted = |digit: u8| digit | DECIMAL_POINT;
+        assert_eq!(
+            countdown(Duration::seconds(3600 + 2 * 60 + 3)),
+            [BLANK, BLANK, BLANK, dotted(1), 0, dotted(2), 0, 3]
+        );
+        // under an hour we only show minutes and seconds
+        assert_eq!(
+            countdown(Duration::seconds(59 * 60 + 59)),
+            [BLANK, BLANK, BLANK, BLANK, 5, dotted(9), 5, 9]
+        );
+        assert_eq!(
+            countdown(Duration::seconds(-65)),
+            [BLANK, BLANK, BLANK, BLANK, 0xA, dotted(1), 0, 5]
+        );
+        assert_eq!(
+            countdown(Duration::seconds(-(2 * 3600 + 5))),
+            [BLANK, BLANK, 0xA, dotted(2), 0, dotted(0), 0, 5]
+        );
+    }
+
+    #[test]
+    fn writes_clocks_from_the_right() {
+        let mut display = segment_display(SegmentMode::CodeB);
+        display.write_clock(12, 34, true);
+        assert_eq!(display.digits[4..], [1, 2 | DECIMAL_POINT, 3, 4]);
+        // digit 0 is the right most of each module
+        assert_eq!(position(0, 0), 7);
+        assert_eq!(position(1, 7), 8);
+    }
+}
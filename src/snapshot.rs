@@ -0,0 +1,119 @@
+use anyhow::anyhow;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// ASCII art shades, from off to fully lit
+const ASCII_SHADES: [char; 4] = ['.', ':', '+', '#'];
+
+/// A copy of what our display is showing, each pixel is a brightness from 0 (off) to 255
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn new(width: usize, height: usize) -> Snapshot {
+        Snapshot {
+            width,
+            height,
+            pixels: vec![0; width * height],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[(y * self.width) + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, brightness: u8) {
+        self.pixels[(y * self.width) + x] = brightness;
+    }
+
+    /// Save ourselves in the format matching our extension: `png`, `pbm` or `txt`
+    pub fn save(&self, path: &Path, scale: usize) -> anyhow::Result<()> {
+        let data = match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => self.to_png(scale)?,
+            Some("pbm") => self.to_pbm(scale).into_bytes(),
+            Some("txt") => self.to_ascii(scale).into_bytes(),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown snapshot format for {path:?}, expected png, pbm or txt"
+                ))
+            }
+        };
+        fs::write(path, data)?;
+
+        Ok(())
+    }
+
+    /// 8-bit grayscale PNG, each pixel becomes a `scale` x `scale` square
+    pub fn to_png(&self, scale: usize) -> anyhow::Result<Vec<u8>> {
+        let scale = scale.max(1);
+        let (width, height) = (self.width * scale, self.height * scale);
+
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut image = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                image.push(self.get(x / scale, y / scale));
+            }
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image)?;
+        writer.finish()?;
+
+        Ok(data)
+    }
+
+    /// Plain (P1) PBM so it's easy to diff, any lit pixel is black
+    pub fn to_pbm(&self, scale: usize) -> String {
+        let scale = scale.max(1);
+        let mut pbm = format!("P1\n{} {}\n", self.width * scale, self.height * scale);
+        for y in 0..(self.height * scale) {
+            let row: Vec<&str> = (0..(self.width * scale))
+                .map(|x| {
+                    if self.get(x / scale, y / scale) > 0 {
+                        "1"
+                    } else {
+                        "0"
+                    }
+                })
+                .collect();
+            let _ = writeln!(pbm, "{}", row.join(" "));
+        }
+
+        pbm
+    }
+
+    /// One character per pixel, `.` is off and `#` is fully lit
+    pub fn to_ascii(&self, scale: usize) -> String {
+        let scale = scale.max(1);
+        let mut ascii = String::with_capacity((self.width * scale + 1) * self.height * scale);
+        for y in 0..(self.height * scale) {
+            for x in 0..(self.width * scale) {
+                ascii.push(shade(self.get(x / scale, y / scale)));
+            }
+            ascii.push('\n');
+        }
+
+        ascii
+    }
+}
+
+fn shade(brightness: u8) -> char {
+    match brightness {
+        0 => ASCII_SHADES[0],
+        255 => ASCII_SHADES[ASCII_SHADES.len() - 1],
+        partial => {
+            let levels = ASCII_SHADES.len() - 2;
+            ASCII_SHADES[1 + ((partial as usize * levels) / 255).min(levels - 1)]
+        }
+    }
+}
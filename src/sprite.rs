@@ -0,0 +1,407 @@
+use anyhow::anyhow;
+use bitvec::vec::BitVec;
+use chrono::{DateTime, Utc};
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::geometry::{Point, Size};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::Pixel;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long each frame of an animated PBM shows, since PBMs can't say
+const PBM_FRAME_DURATION: Duration = Duration::from_millis(500);
+
+/// Far bigger than anything we'd draw, so a bad PBM header can't have us allocate gigabytes
+const MAX_PBM_PIXELS: usize = 1 << 20;
+
+/// The icons we ship, by name
+const BUILTIN: [(&str, &str); 12] = [
+    ("sun", include_str!("../sprites/sun.txt")),
+    ("moon", include_str!("../sprites/moon.txt")),
+    ("up-arrow", include_str!("../sprites/up-arrow.txt")),
+    ("down-arrow", include_str!("../sprites/down-arrow.txt")),
+    ("cloud", include_str!("../sprites/cloud.txt")),
+    (
+        "partly-cloudy",
+        include_str!("../sprites/partly-cloudy.txt"),
+    ),
+    ("rain", include_str!("../sprites/rain.txt")),
+    ("snow", include_str!("../sprites/snow.txt")),
+    ("thunder", include_str!("../sprites/thunder.txt")),
+    ("fog", include_str!("../sprites/fog.txt")),
+    ("wifi-error", include_str!("../sprites/wifi-error.txt")),
+    ("hourglass", include_str!("../sprites/hourglass.txt")),
+];
+
+lazy_static! {
+    static ref BUILTIN_SPRITES: HashMap<&'static str, Sprite> = BUILTIN
+        .into_iter()
+        .map(|(name, text)| (
+            name,
+            Sprite::from_text(text).expect("Our builtin sprites parse")
+        ))
+        .collect();
+}
+
+/// A 1-bit icon, optionally animated
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    size: Size,
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    /// Row by row, our sprite's width wide
+    pixels: BitVec,
+    duration: Duration,
+}
+
+impl Sprite {
+    fn new(size: Size, frames: Vec<Frame>) -> anyhow::Result<Sprite> {
+        if frames.is_empty() {
+            return Err(anyhow!("Our sprite doesn't have any frames"));
+        }
+        if let Some(frame) = frames
+            .iter()
+            .find(|frame| frame.pixels.len() != (size.width * size.height) as usize)
+        {
+            return Err(anyhow!(
+                "All our frames should be {}x{}, not {} pixels",
+                size.width,
+                size.height,
+                frame.pixels.len()
+            ));
+        }
+        if frames.len() > 1 && frames.iter().any(|frame| frame.duration.is_zero()) {
+            return Err(anyhow!(
+                "Our animated sprite needs a duration for each frame"
+            ));
+        }
+
+        Ok(Sprite { size, frames })
+    }
+
+    /// One of our builtin icons
+    pub fn builtin(name: &str) -> anyhow::Result<&'static Sprite> {
+        BUILTIN_SPRITES
+            .get(name)
+            .ok_or_else(|| anyhow!("We don't have a sprite named {name}"))
+    }
+
+    /// One of our builtin icons by name, or a sprite file
+    pub fn open(name: &str) -> anyhow::Result<Sprite> {
+        match BUILTIN_SPRITES.get(name) {
+            Some(sprite) => Ok(sprite.clone()),
+            None => Sprite::load(Path::new(name)),
+        }
+    }
+
+    /// Load a text (`.txt`) or PBM (`.pbm`) sprite
+    pub fn load(path: &Path) -> anyhow::Result<Sprite> {
+        let bytes = fs::read(path)?;
+        let sprite = match path.extension().and_then(|e| e.to_str()) {
+            Some("txt") => Sprite::from_text(std::str::from_utf8(&bytes)?),
+            Some("pbm") => Sprite::from_pbm(&bytes),
+            _ => Err(anyhow!("Unknown sprite format, expected txt or pbm")),
+        };
+
+        sprite.map_err(|error| anyhow!("Couldn't load our sprite from {path:?}: {error}"))
+    }
+
+    /// Rows of `.` (off) and `#` (lit), like our txt snapshots. Animated sprites start each frame
+    /// with `frame <duration>` (e.g. `frame 250ms` or `frame 2s`), and `//` starts a comment.
+    pub fn from_text(text: &str) -> anyhow::Result<Sprite> {
+        let mut frames: Vec<(Vec<&str>, Duration)> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split("//").next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(duration) = line.strip_prefix("frame") {
+                let duration = parse_duration(duration.trim())
+                    .map_err(|error| anyhow!("Line {}: {error}", number + 1))?;
+                frames.push((vec![], duration));
+            } else if line.chars().all(|c| c == '.' || c == '#') {
+                // a sprite without any `frame`s is a single still frame
+                if frames.is_empty() {
+                    frames.push((vec![], Duration::ZERO));
+                }
+                frames.last_mut().unwrap().0.push(line);
+            } else {
+                return Err(anyhow!(
+                    "Line {}: expected `frame <duration>` or a row of . and #, not {line:?}",
+                    number + 1
+                ));
+            }
+        }
+
+        let (first, _) = frames
+            .first()
+            .ok_or_else(|| anyhow!("Our sprite doesn't have any rows"))?;
+        let size = Size::new(
+            first.first().map_or(0, |row| row.len()) as u32,
+            first.len() as u32,
+        );
+        if let Some(row) = frames
+            .iter()
+            .flat_map(|(rows, _)| rows)
+            .find(|row| row.len() != size.width as usize)
+        {
+            return Err(anyhow!(
+                "All our rows should be {} wide, not {row:?}",
+                size.width
+            ));
+        }
+
+        let frames = frames
+            .into_iter()
+            .map(|(rows, duration)| Frame {
+                pixels: rows.concat().chars().map(|c| c == '#').collect(),
+                duration,
+            })
+            .collect();
+        Sprite::new(size, frames)
+    }
+
+    /// Plain (P1) or raw (P4) PBMs, where black is lit. A file with more than 1 image is animated.
+    pub fn from_pbm(pbm: &[u8]) -> anyhow::Result<Sprite> {
+        let mut size = None;
+        let mut frames = vec![];
+        let mut rest = pbm;
+        while !skip_whitespace(rest).is_empty() {
+            let (frame_size, pixels, remaining) = parse_pbm(skip_whitespace(rest))?;
+            if *size.get_or_insert(frame_size) != frame_size {
+                return Err(anyhow!("All our images should be the same size"));
+            }
+            frames.push(Frame {
+                pixels,
+                duration: PBM_FRAME_DURATION,
+            });
+            rest = remaining;
+        }
+
+        Sprite::new(size.unwrap_or_default(), frames)
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Our frame at `now`, each animation loops from the unix epoch so every display agrees
+    fn frame(&self, now: DateTime<Utc>) -> &Frame {
+        let total: Duration = self.frames.iter().map(|frame| frame.duration).sum();
+        if total.is_zero() {
+            return &self.frames[0];
+        }
+
+        let mut at =
+            Duration::from_millis(now.timestamp_millis() as u64 % total.as_millis() as u64);
+        for frame in &self.frames {
+            if at < frame.duration {
+                return frame;
+            }
+            at -= frame.duration;
+        }
+
+        &self.frames[0]
+    }
+
+    /// Light our pixels for `now` with our top left at `top_left`
+    pub fn draw<D>(
+        &self,
+        now: DateTime<Utc>,
+        top_left: Point,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let width = self.size.width as usize;
+        let pixels = self.frame(now).pixels.iter_ones().map(|i| {
+            let point = Point::new((i % width) as i32, (i / width) as i32);
+            Pixel(top_left + point, BinaryColor::On)
+        });
+
+        target.draw_iter(pixels)
+    }
+}
+
+/// `250ms` or `2s`
+fn parse_duration(duration: &str) -> anyhow::Result<Duration> {
+    let parsed = match duration.strip_suffix("ms") {
+        Some(millis) => millis.trim().parse().ok().map(Duration::from_millis),
+        None => duration
+            .strip_suffix('s')
+            .and_then(|seconds| seconds.trim().parse().ok())
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()),
+    };
+
+    parsed.ok_or_else(|| anyhow!("Expected a duration like 250ms or 2s, not {duration:?}"))
+}
+
+/// 1 image and whatever's after it
+fn parse_pbm(pbm: &[u8]) -> anyhow::Result<(Size, BitVec, &[u8])> {
+    let (magic, rest) = pbm_token(pbm)?;
+    let (width, rest) = pbm_token(rest)?;
+    let (height, rest) = pbm_token(rest)?;
+    let (width, height): (usize, usize) = (
+        std::str::from_utf8(width)?.parse()?,
+        std::str::from_utf8(height)?.parse()?,
+    );
+    let area = width
+        .checked_mul(height)
+        .filter(|&area| area > 0 && area <= MAX_PBM_PIXELS)
+        .ok_or_else(|| anyhow!("Our PBM can't be {width}x{height}"))?;
+
+    match magic {
+        b"P1" => {
+            let mut pixels = BitVec::with_capacity(area);
+            let mut rest = rest;
+            while pixels.len() < area {
+                rest = skip_whitespace(rest);
+                match rest.first() {
+                    Some(b'0') => pixels.push(false),
+                    Some(b'1') => pixels.push(true),
+                    _ => return Err(anyhow!("Our PBM is missing some pixels")),
+                }
+                rest = &rest[1..];
+            }
+
+            Ok((Size::new(width as u32, height as u32), pixels, rest))
+        }
+        b"P4" => {
+            // a single whitespace character separates our header from our bits
+            let rest = rest.get(1..).unwrap_or_default();
+            let row_bytes = width.div_ceil(8);
+            let data = rest
+                .get(..row_bytes * height)
+                .ok_or_else(|| anyhow!("Our PBM is missing some pixels"))?;
+            let pixels = data
+                .chunks(row_bytes)
+                .flat_map(|row| (0..width).map(move |x| row[x / 8] & (0x80 >> (x % 8)) != 0))
+                .collect();
+
+            Ok((
+                Size::new(width as u32, height as u32),
+                pixels,
+                &rest[row_bytes * height..],
+            ))
+        }
+        _ => Err(anyhow!("We only support P1 and P4 PBMs")),
+    }
+}
+
+/// The next header token, skipping whitespace and `#` comments
+fn pbm_token(pbm: &[u8]) -> anyhow::Result<(&[u8], &[u8])> {
+    let pbm = skip_whitespace(pbm);
+    let end = pbm
+        .iter()
+        .position(|b| b.is_ascii_whitespace() || *b == b'#')
+        .unwrap_or(pbm.len());
+    if end == 0 {
+        return Err(anyhow!("Our PBM header is cut short"));
+    }
+
+    Ok((&pbm[..end], &pbm[end..]))
+}
+
+fn skip_whitespace(mut pbm: &[u8]) -> &[u8] {
+    loop {
+        match pbm.first() {
+            Some(b) if b.is_ascii_whitespace() => pbm = &pbm[1..],
+            Some(b'#') => {
+                let end = pbm.iter().position(|&b| b == b'\n').unwrap_or(pbm.len());
+                pbm = &pbm[end..];
+            }
+            _ => return pbm,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::testing::ascii_rows;
+
+    fn render(sprite: &Sprite, now: DateTime<Utc>) -> Vec<String> {
+        let size = sprite.size();
+        let mut canvas = Canvas::new(size.width as usize, size.height as usize);
+        sprite.draw(now, Point::zero(), &mut canvas).unwrap();
+
+        ascii_rows(&canvas)
+    }
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(millis).unwrap()
+    }
+
+    #[test]
+    fn builtin_sprites_parse() {
+        for (name, _) in BUILTIN {
+            assert!(Sprite::builtin(name).is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn animates_with_each_frames_duration() {
+        let sprite = Sprite::from_text(
+            "// a blinking dot
+            frame 100ms
+            #.
+            frame 300ms
+            .#",
+        )
+        .unwrap();
+
+        assert_eq!(render(&sprite, at(0)), ["#."]);
+        assert_eq!(render(&sprite, at(99)), ["#."]);
+        assert_eq!(render(&sprite, at(100)), [".#"]);
+        assert_eq!(render(&sprite, at(399)), [".#"]);
+        assert_eq!(render(&sprite, at(400)), ["#."]);
+    }
+
+    #[test]
+    fn pbms_match_our_text() {
+        let text = Sprite::from_text("#..\n.#.\n..#").unwrap();
+        let plain = Sprite::from_pbm(b"P1\n# a diagonal\n3 3\n1 0 0\n0 1 0\n0 0 1\n").unwrap();
+        let raw = Sprite::from_pbm(b"P4 3 3\n\x80\x40\x20").unwrap();
+
+        assert_eq!(render(&plain, at(0)), render(&text, at(0)));
+        assert_eq!(render(&raw, at(0)), render(&text, at(0)));
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        assert!(Sprite::from_text("##\n#").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_and_huge_pbms() {
+        assert!(Sprite::from_pbm(
+            b"P4 0 1
+"
+        )
+        .is_err());
+        assert!(Sprite::from_pbm(
+            b"P1 1 0
+"
+        )
+        .is_err());
+        assert!(Sprite::from_pbm(
+            b"P1 99999999 99999999
+1"
+        )
+        .is_err());
+        assert!(Sprite::from_pbm(
+            b"P4 18446744073709551615 2
+"
+        )
+        .is_err());
+    }
+}
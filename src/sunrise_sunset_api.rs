@@ -1,7 +1,20 @@
+use crate::daylight::Location;
+
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::time::Duration;
+
+lazy_static! {
+    /// Gives up on our sunrise API rather than leaving our daylight hanging
+    static ref CLIENT: Client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Our HTTP client builds");
+}
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct DaylightCollection {
@@ -10,10 +23,12 @@ pub struct DaylightCollection {
     pub status: Status,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct DaylightResponse {
     pub sunrise: DateTime<Utc>,
     pub sunset: DateTime<Utc>,
+    pub civil_twilight_begin: DateTime<Utc>,
+    pub civil_twilight_end: DateTime<Utc>,
     pub day_length: usize,
 }
 
@@ -32,9 +47,14 @@ impl Status {
     }
 }
 
-pub async fn query_daylight<S: Display>(date: &Option<S>) -> anyhow::Result<DaylightResponse> {
+pub async fn query_daylight<S: Display>(
+    location: Location,
+    date: &Option<S>,
+) -> anyhow::Result<DaylightResponse> {
     let url = format!(
-        "https://api.sunrise-sunset.org/json?lat=40.743722&lng=-73.978020&formatted=0{}",
+        "https://api.sunrise-sunset.org/json?lat={}&lng={}&formatted=0{}",
+        location.latitude,
+        location.longitude,
         date.as_ref()
             .map(|d| format!("&date={d}"))
             .unwrap_or_default()
@@ -42,7 +62,9 @@ pub async fn query_daylight<S: Display>(date: &Option<S>) -> anyhow::Result<Dayl
 
     log::debug!("Querying Daylight: {url}");
 
-    let collection = reqwest::get(url)
+    let collection = CLIENT
+        .get(url)
+        .send()
         .await?
         .json::<DaylightCollection>()
         .await?;
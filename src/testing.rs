@@ -0,0 +1,312 @@
+//! Renders our display pipeline into decoded frames and compares them against the golden frames
+//! in `tests/golden`. Run with `BLESS_GOLDENS=1` to write new goldens after an intended change.
+
+use crate::board::Board;
+use crate::canvas::Canvas;
+use crate::clock::Clock;
+use crate::daylight::Daylight;
+use crate::dot_matrix::{DotMatrix, CHAINED_SEGMENTS};
+use crate::gtfs_realtime::{
+    FeedEntity, FeedHeader, FeedMessage, StopTimeEvent, StopTimeUpdate, TripDescriptor, TripUpdate,
+};
+use crate::max7219::decoder::ChainDecoder;
+use crate::max7219::{Max7219, Transport};
+use crate::scene::{Context, Scene};
+use crate::snapshot::Snapshot;
+use crate::timer::Timers;
+use crate::weather::Weather;
+
+use chrono::{DateTime, Utc};
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const BLESS: &str = "BLESS_GOLDENS";
+
+/// Decodes everything written to it like a real chain would, keeping every finished frame
+#[derive(Clone)]
+pub struct RecordingTransport {
+    decoder: Arc<Mutex<ChainDecoder>>,
+    frames: Arc<Mutex<Vec<Snapshot>>>,
+}
+
+impl RecordingTransport {
+    pub fn new(chained_segments: usize) -> RecordingTransport {
+        RecordingTransport {
+            decoder: Arc::new(Mutex::new(ChainDecoder::new(chained_segments))),
+            frames: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    pub fn last_frame(&self) -> Snapshot {
+        self.frames
+            .lock()
+            .unwrap()
+            .last()
+            .cloned()
+            .expect("Nothing was written to our chain")
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let mut decoder = self.decoder.lock().unwrap();
+        if decoder.write(data) {
+            self.frames.lock().unwrap().push(decoder.snapshot());
+        }
+
+        Ok(())
+    }
+}
+
+/// A time like `2024-06-01T12:00:00Z`
+pub fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+}
+
+/// Wait (up to a second) for something our background tasks are doing, like a poller's first fetch
+pub async fn wait_until(done: impl Fn() -> bool) {
+    for _ in 0..100 {
+        if done() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("We waited a second and it never happened");
+}
+
+/// Everything our scenes draw from, like we'd have before we've fetched anything unless we say
+/// otherwise
+pub struct TestContext {
+    pub now: DateTime<Utc>,
+    pub daylight: Daylight,
+    pub weather: Weather,
+    pub timers: Timers,
+}
+
+impl TestContext {
+    pub fn new(now: DateTime<Utc>) -> TestContext {
+        TestContext {
+            now,
+            daylight: Daylight::default(),
+            weather: Weather::default(),
+            timers: Timers::default(),
+        }
+    }
+
+    pub fn weather(self, weather: Weather) -> TestContext {
+        TestContext { weather, ..self }
+    }
+
+    pub fn timers(self, timers: Timers) -> TestContext {
+        TestContext { timers, ..self }
+    }
+
+    pub fn context(&self) -> Context<'_> {
+        Context {
+            now: self.now,
+            daylight: &self.daylight,
+            weather: &self.weather,
+            timers: &self.timers,
+        }
+    }
+
+    /// What our chain would show after drawing `scene`
+    pub fn render(&self, scene: &mut dyn Scene) -> Snapshot {
+        render_context(scene, &self.context())
+    }
+}
+
+/// Each row of our canvas, lit pixels as `#` and the rest as `.`
+pub fn ascii_rows(canvas: &Canvas) -> Vec<String> {
+    (0..canvas.height() as i32)
+        .map(|y| {
+            (0..canvas.width() as i32)
+                .map(|x| if canvas.get(x, y) { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+/// A dot matrix on a recording chain
+pub fn dot_matrix() -> (DotMatrix, RecordingTransport) {
+    let transport = RecordingTransport::new(CHAINED_SEGMENTS);
+    let max = Max7219::new(CHAINED_SEGMENTS, Box::new(transport.clone()));
+
+    (DotMatrix::new(max, 0x0).unwrap(), transport)
+}
+
+/// Render our scene the same way our event loop does, returning what our chain would show
+pub fn render_scene(scene: &mut dyn Scene, clock: Clock, daylight: &Daylight) -> Snapshot {
+    let (weather, timers) = (Weather::default(), Timers::default());
+    let context = Context {
+        now: clock.now(),
+        daylight,
+        weather: &weather,
+        timers: &timers,
+    };
+
+    render_context(scene, &context)
+}
+
+/// Render our scene with everything it can draw from
+pub fn render_context(scene: &mut dyn Scene, context: &Context) -> Snapshot {
+    let (matrix, transport) = dot_matrix();
+    let mut board = Board::DotMatrix {
+        matrix,
+        grayscale: None,
+    };
+    board.render(scene, context).unwrap();
+
+    transport.last_frame()
+}
+
+pub fn assert_golden(name: &str, frame: &Snapshot) {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let goldens = [
+        (directory.join(format!("{name}.txt")), frame.to_ascii(1)),
+        (directory.join(format!("{name}.pbm")), frame.to_pbm(1)),
+    ];
+
+    if env::var_os(BLESS).is_some() {
+        fs::create_dir_all(&directory).unwrap();
+        for (path, actual) in goldens {
+            fs::write(path, actual).unwrap();
+        }
+        return;
+    }
+
+    for (path, actual) in goldens {
+        let expected = fs::read_to_string(&path).unwrap_or_else(|error| {
+            panic!("Couldn't read our golden {path:?}, run with {BLESS}=1 to create it: {error}")
+        });
+        assert_eq!(
+            expected,
+            actual,
+            "{path:?} doesn't match, run with {BLESS}=1 if this change is expected\n{}",
+            frame.to_ascii(1)
+        );
+    }
+}
+
+/// A local HTTP server answering every request with the same response, standing in for the APIs
+/// we query
+pub struct StubServer {
+    port: u16,
+    /// The request line of everything we've been asked
+    requests: Arc<Mutex<Vec<String>>>,
+    /// And their bodies
+    bodies: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubServer {
+    pub fn spawn(status: &str, body: &str) -> StubServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (requests, bodies) = (Arc::new(Mutex::new(vec![])), Arc::new(Mutex::new(vec![])));
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+            Connection: close\r\n\r\n{body}",
+            body.len()
+        );
+
+        let (recorded, recorded_bodies) = (requests.clone(), bodies.clone());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(Result::ok) {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // skip our headers
+                let mut length = 0;
+                for line in reader.by_ref().lines().map_while(Result::ok) {
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push(request_line.trim_end().to_string());
+                recorded_bodies
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&body).into_owned());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        StubServer {
+            port,
+            requests,
+            bodies,
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn bodies(&self) -> Vec<String> {
+        self.bodies.lock().unwrap().clone()
+    }
+}
+
+/// A feed with a trip for each of `trains`, a route and how many seconds after `now` it gets to
+/// `stop_id`
+pub fn gtfs_feed(stop_id: &str, now: DateTime<Utc>, trains: &[(&str, i64)]) -> FeedMessage {
+    FeedMessage {
+        header: Some(FeedHeader {
+            gtfs_realtime_version: Some("1.0".to_string()),
+            timestamp: Some(now.timestamp() as u64),
+        }),
+        entity: trains
+            .iter()
+            .enumerate()
+            .map(|(i, (route, seconds))| FeedEntity {
+                id: Some(i.to_string()),
+                trip_update: Some(TripUpdate {
+                    trip: Some(TripDescriptor {
+                        trip_id: Some(format!("trip-{i}")),
+                        route_id: Some(route.to_string()),
+                    }),
+                    stop_time_update: vec![
+                        StopTimeUpdate {
+                            stop_sequence: Some(1),
+                            arrival: None,
+                            departure: Some(StopTimeEvent {
+                                delay: None,
+                                time: Some(now.timestamp() + seconds - 120),
+                            }),
+                            stop_id: Some("101N".to_string()),
+                        },
+                        StopTimeUpdate {
+                            stop_sequence: Some(2),
+                            arrival: Some(StopTimeEvent {
+                                delay: None,
+                                time: Some(now.timestamp() + seconds),
+                            }),
+                            departure: None,
+                            stop_id: Some(stop_id.to_string()),
+                        },
+                    ],
+                }),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,423 @@
+use crate::notification::NotificationConfig;
+use crate::sprite::Sprite;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A countdown, from a `[[timer]]` table in our config or our remote control
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TimerConfig {
+    /// What our timer scenes and remote control call us, like `launch` or `pomodoro`
+    pub name: String,
+    /// When we're done, like `2025-07-04T09:00:00-04:00`
+    pub at: Option<DateTime<Utc>>,
+    /// Or how long we run once we're started, so we can be started again and again
+    pub minutes: Option<u32>,
+    /// A builtin sprite (like `hourglass`) or a sprite file, shown before our time
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub format: TimerFormat,
+    /// What we do once we're done
+    #[serde(default)]
+    pub done: TimerAction,
+}
+
+/// How we show how long is left
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimerFormat {
+    /// Days when we're a day or more away, then hours and minutes, then minutes and seconds
+    #[default]
+    Auto,
+    /// Whole days, rounded up, like `12D`
+    Days,
+    /// `HH:MM`, like our daylight countdown
+    HoursMinutes,
+    /// `MM:SS`, like a kitchen timer
+    MinutesSeconds,
+}
+
+/// What we do once a timer's done
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum TimerAction {
+    /// Flash our displays for a few seconds
+    #[default]
+    Flash,
+    /// Scroll a message over our scenes
+    Message { text: String },
+    /// POST our timer as JSON to a URL, for home automation and the like
+    Publish { url: String },
+    /// Queue a notification, which can wait its turn
+    Notify(NotificationConfig),
+}
+
+impl TimerConfig {
+    /// Whether we POST to a URL once we're done, which only timers from our config can do so our
+    /// remote control can't point us at whatever's on our network
+    pub fn publishes(&self) -> bool {
+        matches!(self.done, TimerAction::Publish { .. })
+    }
+}
+
+/// How long is left on a timer, in its format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remaining {
+    Days(i64),
+    HoursMinutes(usize, usize),
+    MinutesSeconds(usize, usize),
+}
+
+/// A timer that's counting down, or already done
+#[derive(Debug, Clone)]
+pub struct Timer {
+    pub config: TimerConfig,
+    pub target: DateTime<Utc>,
+    pub icon: Option<Sprite>,
+    /// We were started by our remote control, rather than being in our config
+    remote: bool,
+    done: bool,
+}
+
+impl Timer {
+    fn new(config: TimerConfig, now: DateTime<Utc>, remote: bool) -> anyhow::Result<Timer> {
+        let target = match (config.at, config.minutes) {
+            (Some(at), None) => at,
+            (None, Some(minutes)) if minutes > 0 => now + Duration::minutes(minutes as i64),
+            (None, Some(_)) => {
+                return Err(anyhow!("Our timer {} needs at least 1 minute", config.name))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Our timer {} needs either an `at` or `minutes`",
+                    config.name
+                ))
+            }
+        };
+        let icon = match config.icon.as_deref() {
+            // anyone on our network can add a timer, so they only get our builtin icons and can't
+            // have us read whatever file they name
+            Some(icon) if remote => Some(Sprite::builtin(icon)?.clone()),
+            Some(icon) => Some(Sprite::open(icon)?),
+            None => None,
+        };
+
+        Ok(Timer {
+            // timers that were done before we knew about them don't go off
+            done: target <= now,
+            config,
+            target,
+            icon,
+            remote,
+        })
+    }
+
+    pub fn remaining(&self, now: DateTime<Utc>) -> Remaining {
+        let left = (self.target - now).max(Duration::zero());
+        let seconds = left.num_seconds() as usize;
+        let (hours, minutes) = (seconds / 3600, seconds / 60);
+        match self.config.format {
+            TimerFormat::Days => Remaining::Days(days(left)),
+            TimerFormat::Auto if left >= Duration::days(1) => Remaining::Days(days(left)),
+            TimerFormat::HoursMinutes => Remaining::HoursMinutes(hours, minutes % 60),
+            TimerFormat::Auto if left >= Duration::hours(1) => {
+                Remaining::HoursMinutes(hours, minutes % 60)
+            }
+            TimerFormat::Auto | TimerFormat::MinutesSeconds => {
+                Remaining::MinutesSeconds(minutes, seconds % 60)
+            }
+        }
+    }
+}
+
+/// Whole days left, counting the one we're in
+fn days(left: Duration) -> i64 {
+    (left + Duration::days(1) - Duration::nanoseconds(1)).num_days()
+}
+
+/// What we POST when a timer with a `publish` action is done
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TimerEvent {
+    pub timer: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Every timer we know about, shared by all of our displays
+#[derive(Debug, Default)]
+pub struct Timers {
+    /// Timers with `minutes` from our config, which wait for our remote control to start them
+    presets: Vec<TimerConfig>,
+    timers: Vec<Timer>,
+}
+
+impl Timers {
+    /// Our config's timers, timers with an `at` start counting down right away
+    pub fn new(configs: &[TimerConfig], now: DateTime<Utc>) -> anyhow::Result<Timers> {
+        let mut timers = Timers::default();
+        for (i, config) in configs.iter().enumerate() {
+            if configs[..i].iter().any(|other| other.name == config.name) {
+                return Err(anyhow!("We have more than 1 timer named {}", config.name));
+            }
+
+            let timer = Timer::new(config.clone(), now, false)?;
+            if config.minutes.is_some() {
+                timers.presets.push(config.clone());
+            } else {
+                timers.timers.push(timer);
+            }
+        }
+
+        Ok(timers)
+    }
+
+    /// Swap in a new config's timers, keeping the ones our remote control started
+    pub fn reload(&mut self, mut reloaded: Timers) {
+        let remote: Vec<Timer> = self
+            .timers
+            .drain(..)
+            .filter(|timer| timer.remote && reloaded.get(&timer.config.name).is_none())
+            .collect();
+        reloaded.timers.extend(remote);
+        *self = reloaded;
+    }
+
+    /// Start (or restart) one of our presets
+    pub fn start(&mut self, name: &str, now: DateTime<Utc>) -> anyhow::Result<()> {
+        let preset = self
+            .presets
+            .iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| anyhow!("We don't have a timer named {name} to start"))?;
+
+        let timer = Timer::new(preset.clone(), now, true)?;
+        self.insert(timer);
+
+        Ok(())
+    }
+
+    /// Start a new timer from our remote control, replacing any with the same name
+    pub fn add(&mut self, config: TimerConfig, now: DateTime<Utc>) -> anyhow::Result<()> {
+        if config.publishes() {
+            return Err(anyhow!(
+                "Only timers in our config can publish, {} can't",
+                config.name
+            ));
+        }
+
+        let timer = Timer::new(config, now, true)?;
+        self.insert(timer);
+
+        Ok(())
+    }
+
+    fn insert(&mut self, timer: Timer) {
+        self.cancel(&timer.config.name);
+        self.timers.push(timer);
+    }
+
+    /// Returns whether we had a timer named `name`
+    pub fn cancel(&mut self, name: &str) -> bool {
+        let before = self.timers.len();
+        self.timers.retain(|timer| timer.config.name != name);
+
+        self.timers.len() != before
+    }
+
+    /// Our timers that just finished, marking them done
+    pub fn update(&mut self, now: DateTime<Utc>) -> Vec<Timer> {
+        self.timers
+            .iter_mut()
+            .filter(|timer| !timer.done && timer.target <= now)
+            .map(|timer| {
+                timer.done = true;
+                timer.clone()
+            })
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Timer> {
+        self.timers.iter().find(|timer| timer.config.name == name)
+    }
+
+    /// Our timer that'll be done soonest
+    pub fn next(&self) -> Option<&Timer> {
+        self.timers
+            .iter()
+            .filter(|timer| !timer.done)
+            .min_by_key(|timer| timer.target)
+    }
+}
+
+/// POST a finished timer to `url`
+pub async fn publish(url: &str, timer: &Timer) -> anyhow::Result<()> {
+    log::debug!("Publishing {} to {url}", timer.config.name);
+
+    let event = TimerEvent {
+        timer: timer.config.name.clone(),
+        at: timer.target,
+    };
+    reqwest::Client::new()
+        .post(url)
+        .json(&event)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{at, StubServer};
+
+    fn configs(toml: &str) -> Vec<TimerConfig> {
+        #[derive(Deserialize)]
+        struct Config {
+            timer: Vec<TimerConfig>,
+        }
+
+        toml::from_str::<Config>(toml).unwrap().timer
+    }
+
+    const TIMERS: &str = r#"
+        [[timer]]
+        name = "launch"
+        at = "2024-06-01T09:00:00-04:00"
+        done = { type = "message", text = "Liftoff" }
+
+        [[timer]]
+        name = "pomodoro"
+        minutes = 25
+        icon = "hourglass"
+    "#;
+
+    #[test]
+    fn goes_off_once() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut timers = Timers::new(&configs(TIMERS), now).unwrap();
+        assert_eq!(timers.next().unwrap().config.name, "launch");
+        assert!(timers.update(now).is_empty());
+
+        let done = timers.update(at("2024-06-01T13:00:00Z"));
+        assert_eq!(done.len(), 1);
+        assert_eq!(
+            done[0].config.done,
+            TimerAction::Message {
+                text: "Liftoff".to_string()
+            }
+        );
+        assert!(timers.update(at("2024-06-01T13:00:01Z")).is_empty());
+        assert!(timers.next().is_none());
+    }
+
+    #[test]
+    fn starts_presets_and_keeps_them_across_reloads() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut timers = Timers::new(&configs(TIMERS), now).unwrap();
+        // presets don't count down until they're started
+        assert!(timers.get("pomodoro").is_none());
+        assert!(timers.start("tea", now).is_err());
+
+        timers.start("pomodoro", now).unwrap();
+        let pomodoro = timers.get("pomodoro").unwrap();
+        assert_eq!(pomodoro.target, at("2024-06-01T12:25:00Z"));
+        assert_eq!(pomodoro.remaining(now), Remaining::MinutesSeconds(25, 0));
+
+        timers.reload(Timers::new(&configs(TIMERS), now).unwrap());
+        assert!(timers.get("pomodoro").is_some());
+        assert!(timers.cancel("pomodoro"));
+        assert!(timers.get("pomodoro").is_none());
+    }
+
+    #[test]
+    fn formats_what_is_left() {
+        let now = at("2024-06-01T12:00:00Z");
+        let timer = |at: &str, format: &str| {
+            let config = configs(&format!(
+                "[[timer]]\nname = \"t\"\nat = \"{at}\"\nformat = \"{format}\""
+            ));
+            Timer::new(config[0].clone(), now, false)
+                .unwrap()
+                .remaining(now)
+        };
+
+        assert_eq!(timer("2024-06-13T11:00:00Z", "auto"), Remaining::Days(12));
+        assert_eq!(timer("2024-06-02T15:30:00Z", "auto"), Remaining::Days(2));
+        assert_eq!(
+            timer("2024-06-02T11:30:00Z", "auto"),
+            Remaining::HoursMinutes(23, 30)
+        );
+        assert_eq!(
+            timer("2024-06-01T12:09:05Z", "auto"),
+            Remaining::MinutesSeconds(9, 5)
+        );
+        assert_eq!(
+            timer("2024-06-02T15:30:00Z", "hours-minutes"),
+            Remaining::HoursMinutes(27, 30)
+        );
+        assert_eq!(
+            timer("2024-06-01T14:09:05Z", "minutes-seconds"),
+            Remaining::MinutesSeconds(129, 5)
+        );
+    }
+
+    #[tokio::test]
+    async fn publishes_finished_timers() {
+        let server = StubServer::spawn("200 OK", "");
+        let now = at("2024-06-01T12:00:00Z");
+        let timers = Timers::new(&configs(TIMERS), now).unwrap();
+
+        publish(&server.base_url(), timers.get("launch").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(server.requests(), ["POST / HTTP/1.1"]);
+        let body: serde_json::Value = serde_json::from_str(&server.bodies()[0]).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "timer": "launch", "at": "2024-06-01T13:00:00Z" })
+        );
+    }
+
+    #[test]
+    fn only_our_config_publishes() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut timers = Timers::new(&configs(TIMERS), now).unwrap();
+        let publishing = configs(
+            r#"
+            [[timer]]
+            name = "exfiltrate"
+            minutes = 1
+            done = { type = "publish", url = "http://169.254.169.254/" }
+            "#,
+        );
+
+        assert!(timers.add(publishing[0].clone(), now).is_err());
+        assert!(timers.get("exfiltrate").is_none());
+    }
+
+    #[test]
+    fn remote_timers_only_get_builtin_icons() {
+        let now = at("2024-06-01T12:00:00Z");
+        let mut timers = Timers::new(&[], now).unwrap();
+        let added = configs(
+            r#"
+            [[timer]]
+            name = "tea"
+            minutes = 3
+            icon = "hourglass"
+
+            [[timer]]
+            name = "passwords"
+            minutes = 3
+            icon = "/etc/shadow.pbm"
+            "#,
+        );
+
+        timers.add(added[0].clone(), now).unwrap();
+        assert!(timers.add(added[1].clone(), now).is_err());
+        assert!(timers.get("passwords").is_none());
+    }
+}
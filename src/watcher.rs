@@ -0,0 +1,90 @@
+use crate::{send_log, Event};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{self, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::Sender;
+use tokio::time::sleep;
+
+/// Editors save in a few steps (write a temp file, rename it over ours, ...), so we wait for
+/// them to settle before reloading
+const SETTLE: Duration = Duration::from_millis(250);
+
+/// Sends a reload whenever one of our files changes
+pub struct ConfigWatcher {
+    watcher: RecommendedWatcher,
+    files: Arc<Mutex<HashSet<PathBuf>>>,
+    directories: HashSet<PathBuf>,
+}
+
+impl ConfigWatcher {
+    pub fn spawn(tx: &Sender<Event>) -> anyhow::Result<ConfigWatcher> {
+        let (changed_tx, mut changed_rx) = mpsc::unbounded_channel();
+        let files = Arc::new(Mutex::new(HashSet::new()));
+
+        let watched = files.clone();
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if is_change(&event.kind) => {
+                    let watched = watched.lock().unwrap();
+                    if event.paths.iter().any(|path| watched.contains(path)) {
+                        let _ = changed_tx.send(());
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => log::error!("Failed to watch our config: {error}"),
+            })?;
+
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while changed_rx.recv().await.is_some() {
+                sleep(SETTLE).await;
+                while changed_rx.try_recv().is_ok() {}
+                send_log(Event::Reload, &tx).await;
+            }
+        });
+
+        Ok(ConfigWatcher {
+            watcher,
+            files,
+            directories: HashSet::new(),
+        })
+    }
+
+    /// Watch exactly `files`. We watch their directories since editors often replace a file
+    /// rather than write to it.
+    pub fn watch(&mut self, files: &[&Path]) -> anyhow::Result<()> {
+        let files = files
+            .iter()
+            .map(path::absolute)
+            .collect::<Result<HashSet<_>, _>>()?;
+        let directories: HashSet<PathBuf> = files
+            .iter()
+            .filter_map(|file| file.parent())
+            .map(Path::to_path_buf)
+            .collect();
+
+        for directory in self.directories.difference(&directories) {
+            self.watcher.unwatch(directory)?;
+        }
+        for directory in directories.difference(&self.directories) {
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+        log::debug!("Watching {files:?}");
+
+        self.directories = directories;
+        *self.files.lock().unwrap() = files;
+
+        Ok(())
+    }
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    )
+}
@@ -0,0 +1,251 @@
+use crate::daylight::Location;
+use crate::open_meteo_api::{query_weather, CurrentWeather, WeatherResponse};
+use crate::poller::{Polled, Poller};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Where our weather comes from, the `[weather]` section of our config
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherConfig {
+    /// Open-Meteo, or a stub server of our own
+    pub base_url: String,
+    pub unit: TemperatureUnit,
+    /// How long we show a forecast before asking for a new one
+    pub refresh_minutes: u32,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        WeatherConfig {
+            base_url: "https://api.open-meteo.com".to_string(),
+            unit: TemperatureUnit::default(),
+            refresh_minutes: 15,
+        }
+    }
+}
+
+impl WeatherConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.refresh_minutes == 0 {
+            return Err(anyhow!(
+                "Our weather's refresh_minutes needs to be at least 1"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh_minutes as u64 * 60)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemperatureUnit {
+    Celsius,
+    #[default]
+    Fahrenheit,
+}
+
+/// What we know about the weather where we are
+#[derive(Debug, Clone, Default)]
+pub enum Weather {
+    Known(WeatherResponse),
+    #[default]
+    Unknown,
+}
+
+impl Weather {
+    pub fn current(&self) -> Option<&CurrentWeather> {
+        match self {
+            Weather::Known(response) => Some(&response.current),
+            Weather::Unknown => None,
+        }
+    }
+
+    /// Today's high and low
+    pub fn high_low(&self) -> Option<(f64, f64)> {
+        match self {
+            Weather::Known(response) => Some((
+                *response.daily.temperature_2m_max.first()?,
+                *response.daily.temperature_2m_min.first()?,
+            )),
+            Weather::Unknown => None,
+        }
+    }
+
+    /// The name of our builtin sprite for the current conditions, or our connection being down
+    /// when we don't know them
+    pub fn icon(&self) -> &'static str {
+        let Some(current) = self.current() else {
+            return "wifi-error";
+        };
+
+        // https://open-meteo.com/en/docs#weather_variable_documentation
+        match current.weather_code {
+            0 if current.is_day == 0 => "moon",
+            0 => "sun",
+            1 | 2 => "partly-cloudy",
+            45 | 48 => "fog",
+            51..=67 | 80..=82 => "rain",
+            71..=77 | 85 | 86 => "snow",
+            95..=99 => "thunder",
+            _ => "cloud",
+        }
+    }
+}
+
+/// Keeps our forecast fresh in the background, so we never wait on Open-Meteo to draw
+pub struct Forecast {
+    poller: Poller<Option<Fetched>>,
+    /// How long a forecast is good for
+    refresh: Duration,
+}
+
+/// A forecast and when we got it
+struct Fetched {
+    at: DateTime<Utc>,
+    response: WeatherResponse,
+}
+
+impl Forecast {
+    pub fn spawn(location: Location, config: &WeatherConfig) -> Forecast {
+        let config = config.clone();
+        let refresh = config.refresh();
+        let poller = Poller::spawn("the weather".to_string(), refresh, move || {
+            fetch(location, config.clone())
+        });
+
+        Forecast { poller, refresh }
+    }
+
+    /// Our latest forecast, or nothing while we can't get one
+    pub fn weather(&self) -> Weather {
+        // how old our forecast is goes by the real time, even when our clock is warped
+        self.weather_at(Utc::now())
+    }
+
+    /// Our latest forecast at `now`. While Open-Meteo is failing we keep showing what we last got
+    /// until it's older than our refresh, when it would have been replaced.
+    fn weather_at(&self, now: DateTime<Utc>) -> Weather {
+        let polled = self.poller.lock();
+        let Some(fetched) = &polled.value else {
+            return Weather::Unknown;
+        };
+        let age = (now - fetched.at).to_std().unwrap_or_default();
+        if polled.failing && age > self.refresh {
+            return Weather::Unknown;
+        }
+
+        Weather::Known(fetched.response.clone())
+    }
+}
+
+async fn fetch(
+    location: Location,
+    config: WeatherConfig,
+) -> anyhow::Result<Polled<Option<Fetched>>> {
+    let response = query_weather(location, &config).await?;
+
+    Ok(Polled {
+        value: Some(Fetched {
+            at: Utc::now(),
+            response,
+        }),
+        failing: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{at, wait_until, StubServer};
+
+    const RESPONSE: &str = r#"{
+        "latitude": 40.74,
+        "longitude": -73.98,
+        "current": { "time": "2024-06-01T09:30", "temperature_2m": 71.6, "weather_code": 61, "is_day": 1 },
+        "daily": { "time": ["2024-06-01"], "temperature_2m_max": [75.2], "temperature_2m_min": [60.1] }
+    }"#;
+
+    fn config(server: &StubServer) -> WeatherConfig {
+        WeatherConfig {
+            base_url: server.base_url(),
+            ..WeatherConfig::default()
+        }
+    }
+
+    /// Our forecast once it's heard back from `server`
+    async fn forecast(server: &StubServer) -> Forecast {
+        let forecast = Forecast::spawn(Location::default(), &config(server));
+        wait_until(|| {
+            let polled = forecast.poller.lock();
+            polled.value.is_some() || polled.failing
+        })
+        .await;
+
+        forecast
+    }
+
+    #[tokio::test]
+    async fn queries_our_base_url() {
+        let server = StubServer::spawn("200 OK", RESPONSE);
+        let weather = forecast(&server).await.weather();
+
+        assert_eq!(weather.current().unwrap().temperature_2m, 71.6);
+        assert_eq!(weather.high_low(), Some((75.2, 60.1)));
+        assert_eq!(weather.icon(), "rain");
+
+        let request = server.requests().pop().unwrap();
+        assert!(request.starts_with("GET /v1/forecast?latitude=40.743722&longitude=-73.97802&"));
+        assert!(request.contains("temperature_unit=fahrenheit"));
+        // we don't ask again until our forecast is stale
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_when_we_get_an_error() {
+        let server = StubServer::spawn(
+            "400 Bad Request",
+            r#"{"error": true, "reason": "Latitude must be in range of -90 to 90"}"#,
+        );
+        let weather = forecast(&server).await.weather();
+
+        assert!(weather.current().is_none());
+        assert_eq!(weather.icon(), "wifi-error");
+    }
+
+    #[test]
+    fn keeps_our_forecast_while_failing_until_its_stale() {
+        let fetched = at("2024-06-01T12:00:00Z");
+        let forecast = Forecast {
+            poller: Poller::fixed(Some(Fetched {
+                at: fetched,
+                response: serde_json::from_str(RESPONSE).unwrap(),
+            })),
+            refresh: WeatherConfig::default().refresh(),
+        };
+        forecast.poller.lock().failing = true;
+
+        let weather = forecast.weather_at(at("2024-06-01T12:15:00Z"));
+        assert_eq!(weather.current().unwrap().temperature_2m, 71.6);
+
+        let weather = forecast.weather_at(at("2024-06-01T12:16:00Z"));
+        assert!(weather.current().is_none());
+    }
+
+    #[test]
+    fn needs_a_refresh() {
+        let config = WeatherConfig {
+            refresh_minutes: 0,
+            ..WeatherConfig::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(WeatherConfig::default().validate().is_ok());
+    }
+}